@@ -0,0 +1,2 @@
+ALTER TABLE connections ADD COLUMN safety_level TEXT NOT NULL DEFAULT 'unrestricted'
+    CHECK (safety_level IN ('read_only', 'confirm_destructive', 'unrestricted'));
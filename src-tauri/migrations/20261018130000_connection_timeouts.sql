@@ -0,0 +1,3 @@
+ALTER TABLE connections ADD COLUMN statement_timeout_ms INTEGER;
+ALTER TABLE connections ADD COLUMN lock_timeout_ms INTEGER;
+ALTER TABLE connections ADD COLUMN idle_in_transaction_timeout_ms INTEGER;
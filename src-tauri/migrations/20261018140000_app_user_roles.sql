@@ -0,0 +1,12 @@
+-- Roles were free text defaulting to 'user'; map anything unknown to editor.
+UPDATE app_users SET role = 'editor' WHERE role IS NULL OR role NOT IN ('admin', 'editor', 'viewer');
+
+-- The column still defaults to 'user', and changing a default in SQLite means
+-- rebuilding the table, so rows inserted without a valid role become editors.
+CREATE TRIGGER app_users_default_role AFTER INSERT ON app_users
+WHEN NEW.role IS NULL OR NEW.role NOT IN ('admin', 'editor', 'viewer')
+BEGIN
+    UPDATE app_users SET role = 'editor' WHERE user_id = NEW.user_id;
+END;
+
+ALTER TABLE app_users ADD COLUMN must_change_password INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,16 @@
+ALTER TABLE app_users ADD COLUMN failed_login_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE app_users ADD COLUMN locked_until DATETIME;
+
+-- Single-row table holding the password policy and lockout rules.
+CREATE TABLE security_settings (
+    settings_id INTEGER PRIMARY KEY CHECK (settings_id = 1),
+    min_password_length INTEGER NOT NULL DEFAULT 8,
+    require_uppercase INTEGER NOT NULL DEFAULT 1,
+    require_lowercase INTEGER NOT NULL DEFAULT 1,
+    require_digit INTEGER NOT NULL DEFAULT 1,
+    require_symbol INTEGER NOT NULL DEFAULT 0,
+    max_failed_logins INTEGER NOT NULL DEFAULT 5,
+    lockout_minutes INTEGER NOT NULL DEFAULT 15
+);
+
+INSERT INTO security_settings (settings_id) VALUES (1);
@@ -0,0 +1,14 @@
+-- TOTP secret, encrypted like connection passwords. Set but not enabled while
+-- enrolment awaits its first code.
+ALTER TABLE app_users ADD COLUMN totp_secret TEXT;
+ALTER TABLE app_users ADD COLUMN totp_enabled INTEGER NOT NULL DEFAULT 0;
+-- Last accepted time step, so a code cannot be replayed.
+ALTER TABLE app_users ADD COLUMN totp_last_step INTEGER;
+
+CREATE TABLE app_user_recovery_codes (
+    code_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    code_hash TEXT NOT NULL,
+    used_at DATETIME,
+    FOREIGN KEY (user_id) REFERENCES app_users(user_id) ON DELETE CASCADE
+);
@@ -0,0 +1,18 @@
+CREATE TABLE connection_shares (
+    connection_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    permission TEXT NOT NULL CHECK (permission IN ('use', 'edit', 'owner')),
+    granted_by INTEGER,
+    granted_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (connection_id, user_id),
+    FOREIGN KEY (connection_id) REFERENCES connections(connection_id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES app_users(user_id) ON DELETE CASCADE,
+    FOREIGN KEY (granted_by) REFERENCES app_users(user_id) ON DELETE SET NULL
+);
+
+-- Every connection a user may see: the ones they created, as owner, plus the
+-- ones shared with them.
+CREATE VIEW accessible_connections AS
+SELECT connection_id, user_id, 'owner' AS permission FROM connections
+UNION ALL
+SELECT connection_id, user_id, permission FROM connection_shares;
@@ -0,0 +1,7 @@
+-- No foreign key: entries must outlive the connection they refer to.
+ALTER TABLE app_user_logs ADD COLUMN connection_id INTEGER;
+
+CREATE INDEX idx_app_user_logs_timestamp ON app_user_logs (timestamp);
+CREATE INDEX idx_app_user_logs_user ON app_user_logs (user_id, timestamp);
+CREATE INDEX idx_app_user_logs_action ON app_user_logs (action_type, timestamp);
+CREATE INDEX idx_app_user_logs_connection ON app_user_logs (connection_id, timestamp);
@@ -0,0 +1,38 @@
+-- Rebuilt without the cascading foreign key: deleting a user must not take
+-- their audit entries, and the hash chain, with them.
+CREATE TABLE app_user_logs_chained (
+    log_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    action_type TEXT NOT NULL,
+    details TEXT,
+    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+    connection_id INTEGER,
+    -- entry_hash of the previous entry, NULL for the first.
+    prev_hash TEXT,
+    -- SHA-256 over prev_hash and this entry's content. Existing entries are
+    -- chained on the next start.
+    entry_hash TEXT
+);
+
+INSERT INTO app_user_logs_chained (log_id, user_id, action_type, details, timestamp, connection_id)
+SELECT log_id, user_id, action_type, details, timestamp, connection_id FROM app_user_logs;
+
+DROP TABLE app_user_logs;
+ALTER TABLE app_user_logs_chained RENAME TO app_user_logs;
+
+CREATE INDEX idx_app_user_logs_timestamp ON app_user_logs (timestamp);
+CREATE INDEX idx_app_user_logs_user ON app_user_logs (user_id, timestamp);
+CREATE INDEX idx_app_user_logs_action ON app_user_logs (action_type, timestamp);
+CREATE INDEX idx_app_user_logs_connection ON app_user_logs (connection_id, timestamp);
+
+-- Entries are append-only once chained.
+CREATE TRIGGER app_user_logs_no_update BEFORE UPDATE ON app_user_logs
+WHEN OLD.entry_hash IS NOT NULL
+BEGIN
+    SELECT RAISE(ABORT, 'Audit log entries cannot be modified');
+END;
+
+CREATE TRIGGER app_user_logs_no_delete BEFORE DELETE ON app_user_logs
+BEGIN
+    SELECT RAISE(ABORT, 'Audit log entries cannot be deleted');
+END;
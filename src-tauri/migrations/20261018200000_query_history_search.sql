@@ -0,0 +1,38 @@
+-- Full-text index over history, kept in sync by the triggers below.
+CREATE VIRTUAL TABLE query_history_fts USING fts5(
+    query_text,
+    content = 'query_history',
+    content_rowid = 'history_id'
+);
+
+INSERT INTO query_history_fts (query_history_fts) VALUES ('rebuild');
+
+CREATE TRIGGER query_history_fts_insert AFTER INSERT ON query_history
+BEGIN
+    INSERT INTO query_history_fts (rowid, query_text) VALUES (NEW.history_id, NEW.query_text);
+END;
+
+CREATE TRIGGER query_history_fts_delete AFTER DELETE ON query_history
+BEGIN
+    INSERT INTO query_history_fts (query_history_fts, rowid, query_text)
+    VALUES ('delete', OLD.history_id, OLD.query_text);
+END;
+
+CREATE TRIGGER query_history_fts_update AFTER UPDATE OF query_text ON query_history
+BEGIN
+    INSERT INTO query_history_fts (query_history_fts, rowid, query_text)
+    VALUES ('delete', OLD.history_id, OLD.query_text);
+    INSERT INTO query_history_fts (rowid, query_text) VALUES (NEW.history_id, NEW.query_text);
+END;
+
+CREATE INDEX idx_query_history_connection ON query_history (connection_id, executed_at);
+CREATE INDEX idx_query_history_executed_at ON query_history (executed_at);
+
+-- Single-row table; NULL disables that limit. Enforced by a background task.
+CREATE TABLE history_retention_settings (
+    settings_id INTEGER PRIMARY KEY CHECK (settings_id = 1),
+    max_age_days INTEGER DEFAULT 90,
+    max_rows INTEGER DEFAULT 50000
+);
+
+INSERT INTO history_retention_settings (settings_id) VALUES (1);
@@ -0,0 +1,21 @@
+-- The first rows returned by a successful run, gzip-compressed JSON.
+CREATE TABLE query_history_results (
+    history_id INTEGER PRIMARY KEY,
+    columns_json TEXT NOT NULL,
+    rows_gzip BLOB NOT NULL,
+    -- Rows kept in the snapshot, and rows the query actually returned.
+    stored_row_count INTEGER NOT NULL,
+    total_row_count INTEGER NOT NULL,
+    FOREIGN KEY (history_id) REFERENCES query_history(history_id) ON DELETE CASCADE
+);
+
+-- Single-row table. Snapshots can hold sensitive data, so they are off by default.
+CREATE TABLE history_result_settings (
+    settings_id INTEGER PRIMARY KEY CHECK (settings_id = 1),
+    enabled INTEGER NOT NULL DEFAULT 0,
+    max_rows INTEGER NOT NULL DEFAULT 100,
+    -- Cap on a snapshot's rows as JSON, before compression.
+    max_bytes INTEGER NOT NULL DEFAULT 1048576
+);
+
+INSERT INTO history_result_settings (settings_id) VALUES (1);
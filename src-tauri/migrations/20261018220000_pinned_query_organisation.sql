@@ -0,0 +1,60 @@
+CREATE TABLE pinned_query_folders (
+    folder_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    folder_name TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES app_users(user_id) ON DELETE CASCADE
+);
+
+-- Rebuilt to record an owner and allow connection-independent queries.
+CREATE TABLE pinned_queries_organised (
+    pinned_query_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    -- NULL when the query can run against any connection.
+    connection_id INTEGER,
+    folder_id INTEGER,
+    query_name TEXT NOT NULL,
+    query_text TEXT NOT NULL,
+    description TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME,
+    FOREIGN KEY (user_id) REFERENCES app_users(user_id) ON DELETE CASCADE,
+    FOREIGN KEY (connection_id) REFERENCES connections(connection_id) ON DELETE CASCADE,
+    FOREIGN KEY (folder_id) REFERENCES pinned_query_folders(folder_id) ON DELETE SET NULL
+);
+
+INSERT INTO pinned_queries_organised
+    (pinned_query_id, user_id, connection_id, query_name, query_text, description, created_at)
+SELECT pq.pinned_query_id, c.user_id, pq.connection_id, pq.query_name, pq.query_text,
+       pq.description, pq.created_at
+FROM pinned_queries pq
+JOIN connections c ON pq.connection_id = c.connection_id;
+
+DROP TABLE pinned_queries;
+ALTER TABLE pinned_queries_organised RENAME TO pinned_queries;
+
+CREATE INDEX idx_pinned_queries_user ON pinned_queries (user_id);
+CREATE INDEX idx_pinned_queries_connection ON pinned_queries (connection_id);
+
+CREATE TABLE pinned_query_tags (
+    tag_id INTEGER NOT NULL,
+    pinned_query_id INTEGER NOT NULL,
+    PRIMARY KEY (tag_id, pinned_query_id),
+    FOREIGN KEY (tag_id) REFERENCES tags(tag_id) ON DELETE CASCADE,
+    FOREIGN KEY (pinned_query_id) REFERENCES pinned_queries(pinned_query_id) ON DELETE CASCADE
+);
+
+-- Every saved state of a pinned query's text, numbered from 1.
+CREATE TABLE pinned_query_versions (
+    version_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pinned_query_id INTEGER NOT NULL,
+    version_number INTEGER NOT NULL,
+    query_text TEXT NOT NULL,
+    created_by INTEGER,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (pinned_query_id, version_number),
+    FOREIGN KEY (pinned_query_id) REFERENCES pinned_queries(pinned_query_id) ON DELETE CASCADE,
+    FOREIGN KEY (created_by) REFERENCES app_users(user_id) ON DELETE SET NULL
+);
+
+INSERT INTO pinned_query_versions (pinned_query_id, version_number, query_text, created_by, created_at)
+SELECT pinned_query_id, 1, query_text, user_id, created_at FROM pinned_queries;
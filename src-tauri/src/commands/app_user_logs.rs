@@ -1,15 +1,86 @@
+use crate::commands::sessions::{require_role, user_role, SessionState};
+use crate::error::AppError;
 use crate::models::app_user_logs::AppUserLog;
-use chrono::NaiveDateTime;
+use crate::models::app_users::Role;
+use chrono::{NaiveDateTime, Utc};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 use sqlx::SqlitePool;
 use tauri::State;
+use tokio::sync::Mutex;
+
+const DEFAULT_LOG_PAGE_SIZE: i64 = 100;
+const MAX_LOG_PAGE_SIZE: i64 = 1000;
+
+/// Action types the frontend may record with `create_app_user_log`, for things
+/// only it sees. Everything else is logged by the command that does it.
+const CLIENT_ACTION_TYPES: &[&str] = &["EXPORT_RESULTS", "EXPORT_REPORT"];
+
+/// Held while appending so every entry chains onto the one before it.
+static LOG_CHAIN_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Shared by the listing and the export so both honour the same filters.
+/// Numbered parameters: from, to, user, connection, action types (JSON array).
+const LOG_FILTER_SQL: &str = "FROM app_user_logs l
+     LEFT JOIN app_users u ON l.user_id = u.user_id
+     LEFT JOIN connections c ON l.connection_id = c.connection_id
+     WHERE (?1 IS NULL OR l.timestamp >= ?1)
+       AND (?2 IS NULL OR l.timestamp <= ?2)
+       AND (?3 IS NULL OR l.user_id = ?3)
+       AND (?4 IS NULL OR l.connection_id = ?4)
+       AND (?5 IS NULL OR l.action_type IN (SELECT value FROM json_each(?5)))";
+
+const LOG_COLUMNS_SQL: &str = "SELECT l.log_id, l.user_id, u.username, l.action_type,
+            l.connection_id, c.connection_name, l.details, l.timestamp";
 
 #[derive(serde::Deserialize)]
 pub struct CreateAppUserLogRequest {
-    pub user_id: i64,
     pub action_type: String,
     pub details: Option<String>,
 }
 
+#[derive(Default, serde::Deserialize)]
+pub struct AppUserLogFilter {
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub action_types: Vec<String>,
+    pub user_id: Option<i64>,
+    pub connection_id: Option<i64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(serde::Serialize)]
+pub struct AppUserLogPage {
+    pub entries: Vec<AppUserLog>,
+    /// Number of entries matching the filter, ignoring `limit` and `offset`.
+    pub total: i64,
+}
+
+#[derive(serde::Serialize)]
+pub struct AuditLogVerification {
+    pub valid: bool,
+    pub entries_checked: i64,
+    /// The first entry whose link or content does not match its hash.
+    pub first_broken_log_id: Option<i64>,
+    pub reason: Option<String>,
+    /// Hash of the last entry verified. Kept elsewhere, it also reveals truncation.
+    pub head_hash: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ChainedEntry {
+    log_id: i64,
+    user_id: i64,
+    action_type: String,
+    connection_id: Option<i64>,
+    details: Option<String>,
+    timestamp: Option<String>,
+    prev_hash: Option<String>,
+    entry_hash: Option<String>,
+}
+
 #[derive(serde::Serialize)]
 pub struct UserStatistics {
     pub total_connections: i64,
@@ -21,62 +92,327 @@ pub struct UserStatistics {
     pub user_created_at: Option<NaiveDateTime>,
 }
 
+fn chain_hash(prev_hash: Option<&str>, entry: &ChainedEntry) -> String {
+    let content = json!([
+        prev_hash,
+        entry.user_id,
+        entry.action_type,
+        entry.connection_id,
+        entry.details,
+        entry.timestamp,
+    ]);
+    format!("{:x}", Sha256::digest(content.to_string()))
+}
+
+async fn append_entry(
+    pool: &SqlitePool,
+    user_id: i64,
+    action_type: &str,
+    connection_id: Option<i64>,
+    details: Value,
+) -> Result<i64, AppError> {
+    let _guard = LOG_CHAIN_LOCK.lock().await;
+    let prev_hash: Option<String> =
+        sqlx::query_scalar("SELECT entry_hash FROM app_user_logs ORDER BY log_id DESC LIMIT 1")
+            .fetch_optional(pool)
+            .await?
+            .flatten();
+
+    let entry = ChainedEntry {
+        log_id: 0,
+        user_id,
+        action_type: action_type.to_string(),
+        connection_id,
+        details: Some(details.to_string()),
+        // Same format as CURRENT_TIMESTAMP, so the hash covers the stored text.
+        timestamp: Some(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+        prev_hash: None,
+        entry_hash: None,
+    };
+    let entry_hash = chain_hash(prev_hash.as_deref(), &entry);
+
+    sqlx::query_scalar(
+        "INSERT INTO app_user_logs (user_id, action_type, connection_id, details, timestamp, prev_hash, entry_hash)
+         VALUES (?, ?, ?, ?, ?, ?, ?) RETURNING log_id",
+    )
+    .bind(entry.user_id)
+    .bind(entry.action_type)
+    .bind(entry.connection_id)
+    .bind(entry.details)
+    .bind(entry.timestamp)
+    .bind(prev_hash)
+    .bind(entry_hash)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Records an audit entry. `details` is stored as JSON text; pass
+/// `json!({})` when there is nothing beyond the action itself.
 pub async fn log_action_internal(
     pool: &SqlitePool,
     user_id: i64,
     action_type: &str,
-    details: Option<&str>,
-) -> Result<(), String> {
-    sqlx::query("INSERT INTO app_user_logs (user_id, action_type, details) VALUES (?, ?, ?)")
-        .bind(user_id)
-        .bind(action_type)
-        .bind(details)
-        .execute(pool)
-        .await
-        .map_err(|e| e.to_string())?;
+    connection_id: Option<i64>,
+    details: Value,
+) -> Result<(), AppError> {
+    append_entry(pool, user_id, action_type, connection_id, details).await?;
+    Ok(())
+}
+
+/// Chains entries written before hashing existed. Only runs while the chain is
+/// empty, so an unhashed entry appearing later is reported, not absorbed.
+pub(crate) async fn chain_legacy_logs(pool: &SqlitePool) -> Result<(), AppError> {
+    let _guard = LOG_CHAIN_LOCK.lock().await;
+    let chained: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM app_user_logs WHERE entry_hash IS NOT NULL)",
+    )
+    .fetch_one(pool)
+    .await?;
+    if chained {
+        return Ok(());
+    }
+
+    let entries = sqlx::query_as::<_, ChainedEntry>(
+        "SELECT log_id, user_id, action_type, connection_id, details, timestamp, prev_hash, entry_hash
+         FROM app_user_logs ORDER BY log_id",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut tx = pool.begin().await?;
+    let mut prev_hash: Option<String> = None;
+    for entry in entries {
+        let entry_hash = chain_hash(prev_hash.as_deref(), &entry);
+        sqlx::query("UPDATE app_user_logs SET prev_hash = ?, entry_hash = ? WHERE log_id = ?")
+            .bind(&prev_hash)
+            .bind(&entry_hash)
+            .bind(entry.log_id)
+            .execute(&mut *tx)
+            .await?;
+        prev_hash = Some(entry_hash);
+    }
+    tx.commit().await?;
     Ok(())
 }
 
+/// Records an action the backend cannot observe, such as a file export. Only
+/// the action types in `CLIENT_ACTION_TYPES` are accepted.
 #[tauri::command]
 pub async fn create_app_user_log(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: CreateAppUserLogRequest,
-) -> Result<i64, String> {
-    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+) -> Result<i64, AppError> {
+    let user_id = session.user_id()?;
+    if !CLIENT_ACTION_TYPES.contains(&request.action_type.as_str()) {
+        return Err(AppError::validation(format!(
+            "Action type {} cannot be recorded by the client",
+            request.action_type
+        )));
+    }
 
-    let id = sqlx::query_scalar::<_, i64>(
-        "INSERT INTO app_user_logs (user_id, action_type, details) VALUES (?, ?, ?) RETURNING log_id",
+    append_entry(
+        &pool,
+        user_id,
+        &request.action_type,
+        None,
+        json!({ "message": request.details }),
     )
-    .bind(request.user_id)
-    .bind(request.action_type)
-    .bind(request.details)
-    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| e.to_string())?;
+}
 
-    tx.commit().await.map_err(|e| e.to_string())?;
-    Ok(id)
+/// Walks the hash chain from the oldest entry and reports the first entry
+/// that was edited, inserted out of order, or follows a deleted one.
+#[tauri::command]
+pub async fn verify_audit_log(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+) -> Result<AuditLogVerification, AppError> {
+    let admin_id = require_role(&pool, &session, Role::Admin).await?;
+    let entries = {
+        // Keeps appends from landing between the read and the head hash.
+        let _guard = LOG_CHAIN_LOCK.lock().await;
+        sqlx::query_as::<_, ChainedEntry>(
+            "SELECT log_id, user_id, action_type, connection_id, details, timestamp, prev_hash, entry_hash
+             FROM app_user_logs ORDER BY log_id",
+        )
+        .fetch_all(&*pool)
+        .await?
+    };
+
+    let mut result = AuditLogVerification {
+        valid: true,
+        entries_checked: 0,
+        first_broken_log_id: None,
+        reason: None,
+        head_hash: None,
+    };
+    for entry in &entries {
+        result.entries_checked += 1;
+        let reason = if entry.prev_hash != result.head_hash {
+            Some("Does not link to the previous entry")
+        } else if entry.entry_hash.as_deref()
+            != Some(chain_hash(entry.prev_hash.as_deref(), entry).as_str())
+        {
+            Some("Content does not match its hash")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            result.valid = false;
+            result.first_broken_log_id = Some(entry.log_id);
+            result.reason = Some(reason.to_string());
+            break;
+        }
+        result.head_hash = entry.entry_hash.clone();
+    }
+
+    let _ = log_action_internal(
+        &pool,
+        admin_id,
+        "VERIFY_AUDIT_LOG",
+        None,
+        json!({
+            "valid": result.valid,
+            "entries_checked": result.entries_checked,
+            "first_broken_log_id": result.first_broken_log_id,
+            "head_hash": result.head_hash,
+        }),
+    )
+    .await;
+
+    Ok(result)
+}
+
+/// Admins may read everyone's entries; other users only their own.
+async fn scoped_filter(
+    pool: &SqlitePool,
+    session: &SessionState,
+    mut filter: AppUserLogFilter,
+) -> Result<AppUserLogFilter, AppError> {
+    let user_id = session.user_id()?;
+    if user_role(pool, user_id).await? != Role::Admin {
+        if filter.user_id.is_some_and(|id| id != user_id) {
+            return Err(AppError::forbidden(
+                "Only admins can read other users' activity",
+            ));
+        }
+        filter.user_id = Some(user_id);
+    }
+    Ok(filter)
+}
+
+fn action_types_json(filter: &AppUserLogFilter) -> Option<String> {
+    (!filter.action_types.is_empty()).then(|| json!(filter.action_types).to_string())
 }
 
 #[tauri::command]
 pub async fn get_app_user_logs(
     pool: State<'_, SqlitePool>,
-    user_id: i64,
-) -> Result<Vec<AppUserLog>, String> {
-    sqlx::query_as::<_, AppUserLog>(
-        "SELECT * FROM app_user_logs WHERE user_id = ? ORDER BY timestamp DESC LIMIT 100",
-    )
-    .bind(user_id)
+    session: State<'_, SessionState>,
+    filter: Option<AppUserLogFilter>,
+) -> Result<AppUserLogPage, AppError> {
+    let filter = scoped_filter(&pool, &session, filter.unwrap_or_default()).await?;
+    let action_types = action_types_json(&filter);
+    let limit = filter
+        .limit
+        .unwrap_or(DEFAULT_LOG_PAGE_SIZE)
+        .clamp(1, MAX_LOG_PAGE_SIZE);
+    let offset = filter.offset.unwrap_or(0).max(0);
+
+    let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {LOG_FILTER_SQL}"))
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(filter.user_id)
+        .bind(filter.connection_id)
+        .bind(&action_types)
+        .fetch_one(&*pool)
+        .await?;
+
+    let entries = sqlx::query_as::<_, AppUserLog>(&format!(
+        "{LOG_COLUMNS_SQL} {LOG_FILTER_SQL} ORDER BY l.timestamp DESC, l.log_id DESC LIMIT ?6 OFFSET ?7"
+    ))
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(filter.user_id)
+    .bind(filter.connection_id)
+    .bind(&action_types)
+    .bind(limit)
+    .bind(offset)
     .fetch_all(&*pool)
-    .await
-    .map_err(|e| e.to_string())
+    .await?;
+
+    Ok(AppUserLogPage { entries, total })
+}
+
+/// Quotes `value` for CSV. Values a spreadsheet would read as a formula get a
+/// leading `'` so exports can't smuggle formulas in through usernames or
+/// connection names.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{value}")
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Every entry matching `filter`, oldest first, as CSV. Pagination is ignored.
+#[tauri::command]
+pub async fn export_app_user_logs_csv(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    filter: Option<AppUserLogFilter>,
+) -> Result<String, AppError> {
+    let filter = scoped_filter(&pool, &session, filter.unwrap_or_default()).await?;
+    let action_types = action_types_json(&filter);
+
+    let entries = sqlx::query_as::<_, AppUserLog>(&format!(
+        "{LOG_COLUMNS_SQL} {LOG_FILTER_SQL} ORDER BY l.timestamp, l.log_id"
+    ))
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(filter.user_id)
+    .bind(filter.connection_id)
+    .bind(&action_types)
+    .fetch_all(&*pool)
+    .await?;
+
+    let mut csv = String::from(
+        "log_id,timestamp,user_id,username,action_type,connection_id,connection_name,details\r\n",
+    );
+    for entry in entries {
+        let fields = [
+            entry.log_id.to_string(),
+            entry.timestamp.map(|t| t.to_string()).unwrap_or_default(),
+            entry.user_id.to_string(),
+            entry.username.unwrap_or_default(),
+            entry.action_type,
+            entry
+                .connection_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            entry.connection_name.unwrap_or_default(),
+            entry.details.unwrap_or_default(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+    Ok(csv)
 }
 
 #[tauri::command]
 pub async fn get_user_statistics(
     pool: State<'_, SqlitePool>,
-    user_id: i64,
-) -> Result<UserStatistics, String> {
+    session: State<'_, SessionState>,
+) -> Result<UserStatistics, AppError> {
+    let user_id = session.user_id()?;
     let total_connections: i64 =
         sqlx::query_scalar("SELECT COUNT(*) FROM connections WHERE user_id = ?")
             .bind(user_id)
@@ -106,15 +442,12 @@ pub async fn get_user_statistics(
 
     let error_queries = total_queries - success_queries;
 
-    let pinned_queries: i64 = sqlx::query_scalar(
-        "SELECT COUNT(*) FROM pinned_queries pq 
-         JOIN connections c ON pq.connection_id = c.connection_id 
-         WHERE c.user_id = ?",
-    )
-    .bind(user_id)
-    .fetch_one(&*pool)
-    .await
-    .unwrap_or(0);
+    let pinned_queries: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM pinned_queries WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_one(&*pool)
+            .await
+            .unwrap_or(0);
 
     let last_login: Option<NaiveDateTime> = sqlx::query_scalar(
         "SELECT timestamp FROM app_user_logs WHERE user_id = ? AND action_type = 'LOGIN' ORDER BY timestamp DESC LIMIT 1 OFFSET 1"
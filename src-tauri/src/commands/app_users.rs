@@ -1,16 +1,28 @@
 use crate::{
     commands::app_user_logs::log_action_internal,
-    models::app_users::AppUser,
-    password::{hash_password, verify_password},
+    commands::security_settings::{
+        enforce_password_policy, load_security_settings, MAX_LOCKOUT_MINUTES,
+    },
+    commands::sessions::{require_role, SessionState},
+    commands::two_factor::{reset_two_factor, verify_second_factor, SecondFactor},
+    error::AppError,
+    models::app_users::{AppUser, Role},
+    password::{hash_password, verify_dummy_password, verify_password},
 };
+use chrono::{NaiveDateTime, Utc};
+use serde_json::json;
 use sqlx::SqlitePool;
+use std::time::Duration;
 use tauri::State;
 
+/// Added to every failed login so passwords cannot be guessed at full speed.
+const FAILED_LOGIN_DELAY: Duration = Duration::from_secs(1);
+
 #[derive(serde::Deserialize)]
 pub struct CreateAppUserRequest {
     pub username: String,
     pub password: String,
-    pub role: Option<String>,
+    pub role: Option<Role>,
 }
 
 #[derive(serde::Deserialize)]
@@ -18,33 +30,57 @@ pub struct UpdateAppUserRequest {
     pub user_id: i64,
     pub username: String,
     pub password: Option<String>,
-    pub role: Option<String>,
+    pub role: Option<Role>,
+    /// Turns off the user's 2FA, e.g. after they lost their authenticator.
+    pub reset_totp: Option<bool>,
+}
+
+/// Result of a login attempt. With 2FA enabled, a correct password alone
+/// yields `TotpRequired` and the attempt is repeated with a code.
+#[derive(serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LoginOutcome {
+    Success { user: AppUser },
+    InvalidCredentials,
+    TotpRequired,
+    InvalidTotpCode,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
 }
 
 #[tauri::command]
 pub async fn create_app_user(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: CreateAppUserRequest,
-) -> Result<i64, String> {
-    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+) -> Result<i64, AppError> {
+    let admin_id = require_role(&pool, &session, Role::Admin).await?;
+    let role = request.role.unwrap_or_default();
+    enforce_password_policy(&pool, &request.password).await?;
+    let password_hash = hash_password(&request.password)?;
+    let mut tx = pool.begin().await?;
 
     let id = sqlx::query_scalar::<_, i64>(
         "INSERT INTO app_users (username, password_hash, role) VALUES (?, ?, ?) RETURNING user_id",
     )
     .bind(&request.username)
-    .bind(hash_password(&request.password))
-    .bind(request.role)
+    .bind(password_hash)
+    .bind(role)
     .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?;
 
-    tx.commit().await.map_err(|e| e.to_string())?;
+    tx.commit().await?;
 
     let _ = log_action_internal(
         &pool,
-        id,
+        admin_id,
         "REGISTER",
-        Some(&format!("Username: {}", request.username)),
+        None,
+        json!({ "user_id": id, "username": request.username, "role": role }),
     )
     .await;
 
@@ -54,48 +90,111 @@ pub async fn create_app_user(
 #[tauri::command]
 pub async fn update_app_user(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: UpdateAppUserRequest,
-) -> Result<(), String> {
-    if let Some(new_password) = request.password {
-        let new_hash = hash_password(&new_password);
+) -> Result<(), AppError> {
+    let admin_id = require_role(&pool, &session, Role::Admin).await?;
+    // Without a role the stored one is kept. Keeps at least one admin around:
+    // the one making the change.
+    let role = request.role;
+    if request.user_id == admin_id && role.is_some_and(|role| role != Role::Admin) {
+        return Err(AppError::validation("You cannot change your own role"));
+    }
+
+    let password_changed = request.password.is_some();
+    let result = if let Some(new_password) = request.password {
+        enforce_password_policy(&pool, &new_password).await?;
+        let new_hash = hash_password(&new_password)?;
         sqlx::query(
-            "UPDATE app_users SET username = ?, role = ?, password_hash = ? WHERE user_id = ?",
+            "UPDATE app_users SET username = ?, role = COALESCE(?, role), password_hash = ? WHERE user_id = ?",
         )
-        .bind(request.username)
-        .bind(request.role)
+        .bind(&request.username)
+        .bind(role)
         .bind(new_hash)
         .bind(request.user_id)
         .execute(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?
     } else {
-        sqlx::query("UPDATE app_users SET username = ?, role = ? WHERE user_id = ?")
-            .bind(request.username)
-            .bind(request.role)
+        sqlx::query("UPDATE app_users SET username = ?, role = COALESCE(?, role) WHERE user_id = ?")
+            .bind(&request.username)
+            .bind(role)
             .bind(request.user_id)
             .execute(&*pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?
+    };
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(format!(
+            "User {} not found",
+            request.user_id
+        )));
+    }
+
+    if request.reset_totp.unwrap_or(false) {
+        reset_two_factor(&pool, request.user_id).await?;
+        let _ = log_action_internal(
+            &pool,
+            admin_id,
+            "TOTP_RESET",
+            None,
+            json!({ "user_id": request.user_id }),
+        )
+        .await;
     }
 
+    let _ = log_action_internal(
+        &pool,
+        admin_id,
+        "UPDATE_USER",
+        None,
+        json!({
+            "user_id": request.user_id,
+            "username": request.username,
+            "role": role,
+            "password_changed": password_changed,
+        }),
+    )
+    .await;
+
     Ok(())
 }
 
 #[tauri::command]
-pub async fn delete_app_user(pool: State<'_, SqlitePool>, user_id: i64) -> Result<(), String> {
-    sqlx::query("DELETE FROM app_users WHERE user_id = ?")
+pub async fn delete_app_user(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    user_id: i64,
+) -> Result<(), AppError> {
+    let admin_id = require_role(&pool, &session, Role::Admin).await?;
+    if user_id == admin_id {
+        return Err(AppError::validation("You cannot delete your own account"));
+    }
+    let result = sqlx::query("DELETE FROM app_users WHERE user_id = ?")
         .bind(user_id)
         .execute(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(format!("User {user_id} not found")));
+    }
+
+    let _ = log_action_internal(
+        &pool,
+        admin_id,
+        "DELETE_USER",
+        None,
+        json!({ "user_id": user_id }),
+    )
+    .await;
+
     Ok(())
 }
 
 #[tauri::command]
 pub async fn get_app_users(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     username_search: Option<String>,
-) -> Result<Vec<AppUser>, String> {
+) -> Result<Vec<AppUser>, AppError> {
+    require_role(&pool, &session, Role::Admin).await?;
     let mut sql = String::from("SELECT * FROM app_users");
 
     let has_search = username_search
@@ -118,35 +217,217 @@ pub async fn get_app_users(
         }
     }
 
-    query.fetch_all(&*pool).await.map_err(|e| e.to_string())
+    query.fetch_all(&*pool).await.map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn verify_user_credentials(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     username: String,
     password: String,
-) -> Result<Option<AppUser>, String> {
+    totp_code: Option<String>,
+) -> Result<LoginOutcome, AppError> {
     let result = sqlx::query_as::<_, AppUser>(
-        "SELECT user_id, username, password_hash, role, created_at FROM app_users WHERE username = ?",
+        "SELECT user_id, username, password_hash, role, must_change_password, failed_login_attempts, locked_until, totp_enabled, created_at FROM app_users WHERE username = ?",
     )
     .bind(&username)
     .fetch_optional(&*pool)
     .await
     .map_err(|e| {
         eprintln!("Database error during user retrieval: {e}");
-        e.to_string()
+        AppError::from(e)
     })?;
 
-    if let Some(user) = result {
-        if verify_password(&password, &user.password_hash) {
-            let _ = log_action_internal(&pool, user.user_id, "LOGIN", Some("Success")).await;
+    let Some(mut user) = result else {
+        verify_dummy_password(&password);
+        tokio::time::sleep(FAILED_LOGIN_DELAY).await;
+        return Ok(LoginOutcome::InvalidCredentials);
+    };
 
-            Ok(Some(user))
-        } else {
-            Ok(None)
+    let now = Utc::now().naive_utc();
+    if let Some(locked_until) = user.locked_until.filter(|until| *until > now) {
+        let _ = log_action_internal(
+            &pool,
+            user.user_id,
+            "LOGIN_FAILED",
+            None,
+            json!({ "reason": "Account locked", "locked_until": locked_until }),
+        )
+        .await;
+        // Answered like an unknown username, so a lockout does not reveal
+        // that the account exists.
+        verify_dummy_password(&password);
+        tokio::time::sleep(FAILED_LOGIN_DELAY).await;
+        return Ok(LoginOutcome::InvalidCredentials);
+    }
+
+    if !verify_password(&password, &user.password_hash) {
+        record_failed_login(&pool, &user, now, "Wrong password").await?;
+        tokio::time::sleep(FAILED_LOGIN_DELAY).await;
+        return Ok(LoginOutcome::InvalidCredentials);
+    }
+
+    let mut second_factor = None;
+    if user.totp_enabled {
+        let Some(code) = totp_code.as_deref().filter(|c| !c.trim().is_empty()) else {
+            return Ok(LoginOutcome::TotpRequired);
+        };
+        second_factor = verify_second_factor(&pool, user.user_id, &user.username, code).await?;
+        if second_factor.is_none() {
+            record_failed_login(&pool, &user, now, "Wrong authentication code").await?;
+            tokio::time::sleep(FAILED_LOGIN_DELAY).await;
+            return Ok(LoginOutcome::InvalidTotpCode);
         }
+    }
+
+    if user.failed_login_attempts > 0 || user.locked_until.is_some() {
+        sqlx::query(
+            "UPDATE app_users SET failed_login_attempts = 0, locked_until = NULL WHERE user_id = ?",
+        )
+        .bind(user.user_id)
+        .execute(&*pool)
+        .await?;
+        user.failed_login_attempts = 0;
+        user.locked_until = None;
+    }
+
+    let session_id = session.start(user.user_id, user.must_change_password)?;
+    let factor = match second_factor {
+        Some(SecondFactor::Totp) => Some("authenticator"),
+        Some(SecondFactor::RecoveryCode) => Some("recovery_code"),
+        None => None,
+    };
+    let _ = log_action_internal(
+        &pool,
+        user.user_id,
+        "LOGIN",
+        None,
+        json!({ "session_id": session_id, "second_factor": factor }),
+    )
+    .await;
+
+    Ok(LoginOutcome::Success { user })
+}
+
+/// Counts a wrong password or 2FA code against `user`, locking the account
+/// once the configured limit is reached.
+async fn record_failed_login(
+    pool: &SqlitePool,
+    user: &AppUser,
+    now: NaiveDateTime,
+    reason: &str,
+) -> Result<(), AppError> {
+    let settings = load_security_settings(pool).await?;
+    // Incremented in place so concurrent attempts each count.
+    let attempts: i64 = sqlx::query_scalar(
+        "UPDATE app_users SET failed_login_attempts = failed_login_attempts + 1 WHERE user_id = ? RETURNING failed_login_attempts",
+    )
+    .bind(user.user_id)
+    .fetch_one(pool)
+    .await?;
+
+    let locked_until = if attempts >= settings.max_failed_logins {
+        // Settings stored before the upper bound existed may exceed it.
+        let lockout =
+            chrono::Duration::minutes(settings.lockout_minutes.clamp(1, MAX_LOCKOUT_MINUTES));
+        let locked_until = now
+            .checked_add_signed(lockout)
+            .ok_or_else(|| AppError::internal("Lockout end is out of range"))?;
+        sqlx::query(
+            "UPDATE app_users SET failed_login_attempts = 0, locked_until = ? WHERE user_id = ?",
+        )
+        .bind(locked_until)
+        .bind(user.user_id)
+        .execute(pool)
+        .await?;
+        Some(locked_until)
     } else {
-        Ok(None)
+        None
+    };
+
+    let _ = log_action_internal(
+        pool,
+        user.user_id,
+        "LOGIN_FAILED",
+        None,
+        json!({
+            "reason": reason,
+            "attempt": attempts,
+            "max_attempts": settings.max_failed_logins,
+            "locked_until": locked_until,
+        }),
+    )
+    .await;
+    Ok(())
+}
+
+/// Lifts a lockout before it runs out.
+#[tauri::command]
+pub async fn unlock_app_user(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    user_id: i64,
+) -> Result<(), AppError> {
+    let admin_id = require_role(&pool, &session, Role::Admin).await?;
+    let result = sqlx::query(
+        "UPDATE app_users SET failed_login_attempts = 0, locked_until = NULL WHERE user_id = ?",
+    )
+    .bind(user_id)
+    .execute(&*pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(format!("User {user_id} not found")));
     }
+
+    let _ = log_action_internal(
+        &pool,
+        admin_id,
+        "UNLOCK_USER",
+        None,
+        json!({ "user_id": user_id }),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Changes the signed-in user's own password. Also the only command allowed
+/// while a password change is pending.
+#[tauri::command]
+pub async fn change_password(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    request: ChangePasswordRequest,
+) -> Result<(), AppError> {
+    let user_id = session.signed_in_user_id()?;
+    let current_hash: String =
+        sqlx::query_scalar("SELECT password_hash FROM app_users WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_optional(&*pool)
+            .await?
+            .ok_or_else(|| AppError::auth("Not signed in"))?;
+
+    if !verify_password(&request.current_password, &current_hash) {
+        return Err(AppError::validation("Current password is incorrect"));
+    }
+    if request.new_password == request.current_password {
+        return Err(AppError::validation(
+            "New password must differ from the current one",
+        ));
+    }
+    enforce_password_policy(&pool, &request.new_password).await?;
+
+    sqlx::query(
+        "UPDATE app_users SET password_hash = ?, must_change_password = 0 WHERE user_id = ?",
+    )
+    .bind(hash_password(&request.new_password)?)
+    .bind(user_id)
+    .execute(&*pool)
+    .await?;
+    session.password_changed()?;
+
+    let _ = log_action_internal(&pool, user_id, "PASSWORD_CHANGE", None, json!({})).await;
+
+    Ok(())
 }
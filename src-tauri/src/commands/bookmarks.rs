@@ -1,4 +1,8 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::sessions::{ensure_owner, Owned, SessionState};
+use crate::error::AppError;
 use crate::models::bookmarks::Bookmark;
+use serde_json::json;
 use sqlx::SqlitePool;
 use tauri::State;
 
@@ -13,8 +17,12 @@ pub struct BookmarkRequest {
 #[tauri::command]
 pub async fn toggle_bookmark(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: BookmarkRequest,
-) -> Result<bool, String> {
+) -> Result<bool, AppError> {
+    let user_id = session.user_id()?;
+    ensure_owner(&pool, user_id, Owned::Connection(request.connection_id)).await?;
+
     let existing = sqlx::query_scalar::<_, i64>(
         "SELECT bookmark_id FROM bookmarks WHERE connection_id = ? AND schema_name = ? AND object_name = ? AND object_type = ?"
     )
@@ -23,43 +31,60 @@ pub async fn toggle_bookmark(
     .bind(&request.object_name)
     .bind(&request.object_type)
     .fetch_optional(&*pool)
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?;
 
-    if let Some(id) = existing {
+    let bookmarked = if let Some(id) = existing {
         sqlx::query("DELETE FROM bookmarks WHERE bookmark_id = ?")
             .bind(id)
             .execute(&*pool)
-            .await
-            .map_err(|e| e.to_string())?;
-        Ok(false)
+            .await?;
+        false
     } else {
         sqlx::query(
             "INSERT INTO bookmarks (connection_id, schema_name, object_name, object_type) VALUES (?, ?, ?, ?)"
         )
         .bind(request.connection_id)
-        .bind(request.schema_name)
-        .bind(request.object_name)
-        .bind(request.object_type)
+        .bind(&request.schema_name)
+        .bind(&request.object_name)
+        .bind(&request.object_type)
         .execute(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
-        Ok(true)
-    }
+        .await?;
+        true
+    };
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        if bookmarked {
+            "ADD_BOOKMARK"
+        } else {
+            "REMOVE_BOOKMARK"
+        },
+        Some(request.connection_id),
+        json!({
+            "schema_name": request.schema_name,
+            "object_name": request.object_name,
+            "object_type": request.object_type,
+        }),
+    )
+    .await;
+
+    Ok(bookmarked)
 }
 
 #[tauri::command]
 pub async fn get_bookmarks(
     pool: State<'_, SqlitePool>,
-    user_id: i64,
-) -> Result<Vec<Bookmark>, String> {
+    session: State<'_, SessionState>,
+) -> Result<Vec<Bookmark>, AppError> {
+    let user_id = session.user_id()?;
     sqlx::query_as::<_, Bookmark>(
         "SELECT b.* FROM bookmarks b
-         JOIN connections c ON b.connection_id = c.connection_id
+         JOIN accessible_connections c ON b.connection_id = c.connection_id
          WHERE c.user_id = ?",
     )
     .bind(user_id)
     .fetch_all(&*pool)
     .await
-    .map_err(|e| e.to_string())
+    .map_err(AppError::from)
 }
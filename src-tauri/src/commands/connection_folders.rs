@@ -1,10 +1,13 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::sessions::{ensure_owner, Owned, SessionState};
+use crate::error::AppError;
 use crate::models::connection_folders::ConnectionFolder;
+use serde_json::json;
 use sqlx::SqlitePool;
 use tauri::State;
 
 #[derive(serde::Deserialize)]
 pub struct CreateFolderRequest {
-    pub user_id: i64,
     pub folder_name: String,
 }
 
@@ -17,56 +20,92 @@ pub struct UpdateFolderRequest {
 #[tauri::command]
 pub async fn create_connection_folder(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: CreateFolderRequest,
-) -> Result<i64, String> {
-    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+) -> Result<i64, AppError> {
+    let user_id = session.user_id()?;
+    let mut tx = pool.begin().await?;
     let id = sqlx::query_scalar::<_, i64>(
         "INSERT INTO connection_folders (user_id, folder_name) VALUES (?, ?) RETURNING folder_id",
     )
-    .bind(request.user_id)
-    .bind(request.folder_name)
+    .bind(user_id)
+    .bind(&request.folder_name)
     .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
-    tx.commit().await.map_err(|e| e.to_string())?;
+    .await?;
+    tx.commit().await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "CREATE_FOLDER",
+        None,
+        json!({ "folder_id": id, "folder_name": request.folder_name }),
+    )
+    .await;
+
     Ok(id)
 }
 
 #[tauri::command]
 pub async fn update_connection_folder(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: UpdateFolderRequest,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_owner(&pool, user_id, Owned::Folder(request.folder_id)).await?;
     sqlx::query("UPDATE connection_folders SET folder_name = ? WHERE folder_id = ?")
-        .bind(request.folder_name)
+        .bind(&request.folder_name)
         .bind(request.folder_id)
         .execute(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "UPDATE_FOLDER",
+        None,
+        json!({ "folder_id": request.folder_id, "folder_name": request.folder_name }),
+    )
+    .await;
+
     Ok(())
 }
 
 #[tauri::command]
 pub async fn delete_connection_folder(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     folder_id: i64,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_owner(&pool, user_id, Owned::Folder(folder_id)).await?;
     sqlx::query("DELETE FROM connection_folders WHERE folder_id = ?")
         .bind(folder_id)
         .execute(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "DELETE_FOLDER",
+        None,
+        json!({ "folder_id": folder_id }),
+    )
+    .await;
+
     Ok(())
 }
 
 #[tauri::command]
 pub async fn get_connection_folders(
     pool: State<'_, SqlitePool>,
-    user_id: i64,
-) -> Result<Vec<ConnectionFolder>, String> {
+    session: State<'_, SessionState>,
+) -> Result<Vec<ConnectionFolder>, AppError> {
+    let user_id = session.user_id()?;
     sqlx::query_as::<_, ConnectionFolder>("SELECT * FROM connection_folders WHERE user_id = ?")
         .bind(user_id)
         .fetch_all(&*pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
@@ -0,0 +1,145 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::sessions::{ensure_connection_permission, SessionState};
+use crate::error::AppError;
+use crate::models::connection_shares::{ConnectionShare, SharePermission};
+use serde_json::json;
+use sqlx::SqlitePool;
+use tauri::State;
+
+#[derive(serde::Deserialize)]
+pub struct ShareConnectionRequest {
+    pub connection_id: i64,
+    pub username: String,
+    pub permission: SharePermission,
+}
+
+/// Grants `username` access to a connection, or changes the level of an
+/// existing grant.
+#[tauri::command]
+pub async fn share_connection(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    request: ShareConnectionRequest,
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_connection_permission(
+        &pool,
+        user_id,
+        request.connection_id,
+        SharePermission::Owner,
+    )
+    .await?;
+
+    let grantee: i64 = sqlx::query_scalar("SELECT user_id FROM app_users WHERE username = ?")
+        .bind(&request.username)
+        .fetch_optional(&*pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("User {} not found", request.username)))?;
+    let creator: i64 =
+        sqlx::query_scalar("SELECT user_id FROM connections WHERE connection_id = ?")
+            .bind(request.connection_id)
+            .fetch_one(&*pool)
+            .await?;
+    if grantee == creator {
+        return Err(AppError::validation(
+            "The connection's creator always has owner access",
+        ));
+    }
+
+    sqlx::query(
+        "INSERT INTO connection_shares (connection_id, user_id, permission, granted_by)
+         VALUES (?, ?, ?, ?)
+         ON CONFLICT (connection_id, user_id) DO UPDATE SET
+             permission = excluded.permission,
+             granted_by = excluded.granted_by,
+             granted_at = CURRENT_TIMESTAMP",
+    )
+    .bind(request.connection_id)
+    .bind(grantee)
+    .bind(request.permission)
+    .bind(user_id)
+    .execute(&*pool)
+    .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "SHARE_CONNECTION",
+        Some(request.connection_id),
+        json!({
+            "user_id": grantee,
+            "username": request.username,
+            "permission": request.permission,
+        }),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Revokes a grant. Owners may revoke anyone's; any user may give up their own.
+#[tauri::command]
+pub async fn unshare_connection(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+    user_id: i64,
+) -> Result<(), AppError> {
+    let acting_user_id = session.user_id()?;
+    let required = if user_id == acting_user_id {
+        SharePermission::Use
+    } else {
+        SharePermission::Owner
+    };
+    ensure_connection_permission(&pool, acting_user_id, connection_id, required).await?;
+
+    let result =
+        sqlx::query("DELETE FROM connection_shares WHERE connection_id = ? AND user_id = ?")
+            .bind(connection_id)
+            .bind(user_id)
+            .execute(&*pool)
+            .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(format!(
+            "Connection {connection_id} is not shared with user {user_id}"
+        )));
+    }
+
+    let _ = log_action_internal(
+        &pool,
+        acting_user_id,
+        "UNSHARE_CONNECTION",
+        Some(connection_id),
+        json!({ "user_id": user_id }),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_connection_shares(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+) -> Result<Vec<ConnectionShare>, AppError> {
+    ensure_connection_permission(
+        &pool,
+        session.user_id()?,
+        connection_id,
+        SharePermission::Owner,
+    )
+    .await?;
+
+    sqlx::query_as::<_, ConnectionShare>(
+        "SELECT s.connection_id, s.user_id, u.username, s.permission, s.granted_by, s.granted_at
+         FROM connection_shares s
+         JOIN app_users u ON s.user_id = u.user_id
+         WHERE s.connection_id = ?
+         ORDER BY u.username",
+    )
+    .bind(connection_id)
+    .fetch_all(&*pool)
+    .await
+    .map_err(AppError::from)
+}
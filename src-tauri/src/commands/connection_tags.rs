@@ -1,5 +1,9 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::sessions::{ensure_owner, Owned, SessionState};
+use crate::error::AppError;
 use crate::models::connection_tags::ConnectionTag;
 use crate::models::tags::Tag;
+use serde_json::json;
 use sqlx::SqlitePool;
 use tauri::State;
 
@@ -12,54 +16,93 @@ pub struct AddConnectionTagRequest {
 #[tauri::command]
 pub async fn add_connection_tag(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: AddConnectionTagRequest,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_owner(&pool, user_id, Owned::Tag(request.tag_id)).await?;
+    ensure_owner(&pool, user_id, Owned::Connection(request.connection_id)).await?;
+
     sqlx::query("INSERT OR IGNORE INTO connection_tags (tag_id, connection_id) VALUES (?, ?)")
         .bind(request.tag_id)
         .bind(request.connection_id)
         .execute(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "ADD_CONNECTION_TAG",
+        Some(request.connection_id),
+        json!({ "tag_id": request.tag_id }),
+    )
+    .await;
+
     Ok(())
 }
 
 #[tauri::command]
 pub async fn remove_connection_tag(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     tag_id: i64,
     connection_id: i64,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_owner(&pool, user_id, Owned::Tag(tag_id)).await?;
+    ensure_owner(&pool, user_id, Owned::Connection(connection_id)).await?;
+
     sqlx::query("DELETE FROM connection_tags WHERE tag_id = ? AND connection_id = ?")
         .bind(tag_id)
         .bind(connection_id)
         .execute(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "REMOVE_CONNECTION_TAG",
+        Some(connection_id),
+        json!({ "tag_id": tag_id }),
+    )
+    .await;
+
     Ok(())
 }
 
 #[tauri::command]
 pub async fn get_tags_for_connection(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     connection_id: i64,
-) -> Result<Vec<Tag>, String> {
+) -> Result<Vec<Tag>, AppError> {
+    let user_id = session.user_id()?;
+    ensure_owner(&pool, user_id, Owned::Connection(connection_id)).await?;
+    // Tags are personal; other users' tags on a shared connection stay hidden.
     sqlx::query_as::<_, Tag>(
         "SELECT t.* FROM tags t
          JOIN connection_tags ct ON t.tag_id = ct.tag_id
-         WHERE ct.connection_id = ?",
+         WHERE ct.connection_id = ? AND t.user_id = ?",
     )
     .bind(connection_id)
+    .bind(user_id)
     .fetch_all(&*pool)
     .await
-    .map_err(|e| e.to_string())
+    .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_all_connection_tags(
     pool: State<'_, SqlitePool>,
-) -> Result<Vec<ConnectionTag>, String> {
-    sqlx::query_as::<_, ConnectionTag>("SELECT * FROM connection_tags")
-        .fetch_all(&*pool)
-        .await
-        .map_err(|e| e.to_string())
+    session: State<'_, SessionState>,
+) -> Result<Vec<ConnectionTag>, AppError> {
+    sqlx::query_as::<_, ConnectionTag>(
+        "SELECT ct.* FROM connection_tags ct
+         JOIN tags t ON ct.tag_id = t.tag_id
+         WHERE t.user_id = ?",
+    )
+    .bind(session.user_id()?)
+    .fetch_all(&*pool)
+    .await
+    .map_err(AppError::from)
 }
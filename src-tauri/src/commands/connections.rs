@@ -1,12 +1,15 @@
 use crate::commands::app_user_logs::log_action_internal;
-use crate::models::connections::Connection;
+use crate::commands::sessions::{ensure_connection_permission, ensure_owner, Owned, SessionState};
+use crate::error::AppError;
+use crate::models::connection_shares::SharePermission;
+use crate::models::connections::{Connection, SafetyLevel};
 use crate::password::encrypt_data;
+use serde_json::json;
 use sqlx::SqlitePool;
 use tauri::State;
 
 #[derive(serde::Deserialize)]
 pub struct CreateConnectionRequest {
-    pub user_id: i64,
     pub connection_name: String,
     pub host: String,
     pub port: Option<i64>,
@@ -15,6 +18,10 @@ pub struct CreateConnectionRequest {
     pub db_password: String,
     pub ssl_mode: Option<String>,
     pub folder_id: Option<i64>,
+    pub safety_level: Option<SafetyLevel>,
+    pub statement_timeout_ms: Option<i64>,
+    pub lock_timeout_ms: Option<i64>,
+    pub idle_in_transaction_timeout_ms: Option<i64>,
 }
 
 #[derive(serde::Deserialize)]
@@ -28,40 +35,73 @@ pub struct UpdateConnectionRequest {
     pub db_password: Option<String>,
     pub ssl_mode: Option<String>,
     pub folder_id: Option<i64>,
+    pub safety_level: Option<SafetyLevel>,
+    pub statement_timeout_ms: Option<i64>,
+    pub lock_timeout_ms: Option<i64>,
+    pub idle_in_transaction_timeout_ms: Option<i64>,
+}
+
+/// Rejects negative timeouts before they reach Postgres at connect time.
+pub(crate) fn validate_timeouts(timeouts: [Option<i64>; 3]) -> Result<(), AppError> {
+    if timeouts.into_iter().flatten().any(|ms| ms < 0) {
+        return Err(AppError::validation("Timeouts cannot be negative"));
+    }
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn create_connection(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: CreateConnectionRequest,
-) -> Result<i64, String> {
-    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+) -> Result<i64, AppError> {
+    let user_id = session.user_id()?;
+    validate_timeouts([
+        request.statement_timeout_ms,
+        request.lock_timeout_ms,
+        request.idle_in_transaction_timeout_ms,
+    ])?;
+    if let Some(folder_id) = request.folder_id {
+        ensure_owner(&pool, user_id, Owned::Folder(folder_id)).await?;
+    }
+
+    let mut tx = pool.begin().await?;
 
     let encrypted_pass = encrypt_data(&request.db_password);
 
     let id = sqlx::query_scalar::<_, i64>(
-        "INSERT INTO connections (user_id, connection_name, host, port, db_name, db_user, db_password_encrypted, ssl_mode, folder_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING connection_id",
+        "INSERT INTO connections (user_id, connection_name, host, port, db_name, db_user, db_password_encrypted, ssl_mode, folder_id, safety_level, statement_timeout_ms, lock_timeout_ms, idle_in_transaction_timeout_ms) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING connection_id",
     )
-    .bind(request.user_id)
+    .bind(user_id)
     .bind(&request.connection_name)
-    .bind(request.host)
+    .bind(&request.host)
     .bind(request.port)
-    .bind(request.db_name)
-    .bind(request.db_user)
+    .bind(&request.db_name)
+    .bind(&request.db_user)
     .bind(encrypted_pass)
     .bind(request.ssl_mode)
     .bind(request.folder_id)
+    .bind(request.safety_level.unwrap_or_default())
+    .bind(request.statement_timeout_ms)
+    .bind(request.lock_timeout_ms)
+    .bind(request.idle_in_transaction_timeout_ms)
     .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?;
 
-    tx.commit().await.map_err(|e| e.to_string())?;
+    tx.commit().await?;
 
     let _ = log_action_internal(
         &pool,
-        request.user_id,
+        user_id,
         "CREATE_CONNECTION",
-        Some(&request.connection_name),
+        Some(id),
+        json!({
+            "connection_name": request.connection_name,
+            "host": request.host,
+            "port": request.port,
+            "db_name": request.db_name,
+            "db_user": request.db_user,
+        }),
     )
     .await;
 
@@ -71,37 +111,104 @@ pub async fn create_connection(
 #[tauri::command]
 pub async fn update_connection(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: UpdateConnectionRequest,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    let permission =
+        ensure_connection_permission(&pool, user_id, request.connection_id, SharePermission::Edit)
+            .await?;
+    validate_timeouts([
+        request.statement_timeout_ms,
+        request.lock_timeout_ms,
+        request.idle_in_transaction_timeout_ms,
+    ])?;
+    if let Some(folder_id) = request.folder_id {
+        ensure_owner(&pool, user_id, Owned::Folder(folder_id)).await?;
+    }
+
     let new_password = match &request.db_password {
         Some(p) if !p.is_empty() => Some(encrypt_data(p)),
         _ => None,
     };
 
-    let user_id: Option<i64> = (if let Some(encrypted_pass) = new_password {
-        sqlx::query_scalar("UPDATE connections SET db_password_encrypted = ?, connection_name = ?, host = ?, port = ?, db_name = ?, db_user = ?, ssl_mode = ?, folder_id = ? WHERE connection_id = ? RETURNING user_id")
+    if permission != SharePermission::Owner {
+        let stored =
+            sqlx::query_as::<_, Connection>("SELECT * FROM connections WHERE connection_id = ?")
+                .bind(request.connection_id)
+                .fetch_one(&*pool)
+                .await?;
+
+        // The safety level and timeouts are the owner's guard rails.
+        let changes = |new: Option<i64>, old: Option<i64>| new.is_some() && new != old;
+        if request
+            .safety_level
+            .is_some_and(|level| level != stored.safety_level)
+            || changes(request.statement_timeout_ms, stored.statement_timeout_ms)
+            || changes(request.lock_timeout_ms, stored.lock_timeout_ms)
+            || changes(
+                request.idle_in_transaction_timeout_ms,
+                stored.idle_in_transaction_timeout_ms,
+            )
+        {
+            return Err(AppError::forbidden(
+                "Only the owner can change the safety level or timeouts of a connection",
+            ));
+        }
+
+        // The stored password is sent wherever the connection points, so only
+        // an owner may redirect it or weaken TLS without supplying a new one.
+        if new_password.is_none()
+            && (stored.host != request.host
+                || stored.port != request.port
+                || stored.db_user != request.db_user
+                || stored.ssl_mode != request.ssl_mode)
+        {
+            return Err(AppError::validation(
+                "Enter the password when changing the host, port, user or SSL mode of a shared connection",
+            ));
+        }
+    }
+
+    let password_changed = new_password.is_some();
+
+    // Folders are personal, so only the creator's folder choice is stored.
+    let updated: Option<i64> = (if let Some(encrypted_pass) = new_password {
+        sqlx::query_scalar("UPDATE connections SET db_password_encrypted = ?, connection_name = ?, host = ?, port = ?, db_name = ?, db_user = ?, ssl_mode = ?, folder_id = CASE WHEN user_id = ? THEN ? ELSE folder_id END, safety_level = COALESCE(?, safety_level), statement_timeout_ms = COALESCE(?, statement_timeout_ms), lock_timeout_ms = COALESCE(?, lock_timeout_ms), idle_in_transaction_timeout_ms = COALESCE(?, idle_in_transaction_timeout_ms) WHERE connection_id = ? RETURNING connection_id")
             .bind(encrypted_pass)
     } else {
-        sqlx::query_scalar("UPDATE connections SET connection_name = ?, host = ?, port = ?, db_name = ?, db_user = ?, ssl_mode = ?, folder_id = ? WHERE connection_id = ? RETURNING user_id")
+        sqlx::query_scalar("UPDATE connections SET connection_name = ?, host = ?, port = ?, db_name = ?, db_user = ?, ssl_mode = ?, folder_id = CASE WHEN user_id = ? THEN ? ELSE folder_id END, safety_level = COALESCE(?, safety_level), statement_timeout_ms = COALESCE(?, statement_timeout_ms), lock_timeout_ms = COALESCE(?, lock_timeout_ms), idle_in_transaction_timeout_ms = COALESCE(?, idle_in_transaction_timeout_ms) WHERE connection_id = ? RETURNING connection_id")
     })
     .bind(&request.connection_name)
-    .bind(request.host)
+    .bind(&request.host)
     .bind(request.port)
-    .bind(request.db_name)
-    .bind(request.db_user)
+    .bind(&request.db_name)
+    .bind(&request.db_user)
     .bind(request.ssl_mode)
+    .bind(user_id)
     .bind(request.folder_id)
+    .bind(request.safety_level)
+    .bind(request.statement_timeout_ms)
+    .bind(request.lock_timeout_ms)
+    .bind(request.idle_in_transaction_timeout_ms)
     .bind(request.connection_id)
     .fetch_optional(&*pool)
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?;
 
-    if let Some(uid) = user_id {
+    if updated.is_some() {
         let _ = log_action_internal(
             &pool,
-            uid,
+            user_id,
             "UPDATE_CONNECTION",
-            Some(&request.connection_name),
+            Some(request.connection_id),
+            json!({
+                "connection_name": request.connection_name,
+                "host": request.host,
+                "port": request.port,
+                "db_name": request.db_name,
+                "db_user": request.db_user,
+                "password_changed": password_changed,
+            }),
         )
         .await;
     }
@@ -112,21 +219,26 @@ pub async fn update_connection(
 #[tauri::command]
 pub async fn delete_connection(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     connection_id: i64,
-) -> Result<(), String> {
-    let user_id: Option<i64> =
-        sqlx::query_scalar("DELETE FROM connections WHERE connection_id = ? RETURNING user_id")
-            .bind(connection_id)
-            .fetch_optional(&*pool)
-            .await
-            .map_err(|e| e.to_string())?;
-
-    if let Some(uid) = user_id {
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_connection_permission(&pool, user_id, connection_id, SharePermission::Owner).await?;
+
+    let deleted: Option<i64> = sqlx::query_scalar(
+        "DELETE FROM connections WHERE connection_id = ? RETURNING connection_id",
+    )
+    .bind(connection_id)
+    .fetch_optional(&*pool)
+    .await?;
+
+    if deleted.is_some() {
         let _ = log_action_internal(
             &pool,
-            uid,
+            user_id,
             "DELETE_CONNECTION",
-            Some(&format!("ID: {connection_id}")),
+            Some(connection_id),
+            json!({}),
         )
         .await;
     }
@@ -137,10 +249,21 @@ pub async fn delete_connection(
 #[tauri::command]
 pub async fn get_connections(
     pool: State<'_, SqlitePool>,
-    user_id: i64,
+    session: State<'_, SessionState>,
     with_tags: Option<Vec<i64>>,
-) -> Result<Vec<Connection>, String> {
-    let mut sql = String::from("SELECT * FROM connections WHERE user_id = ?");
+) -> Result<Vec<Connection>, AppError> {
+    let user_id = session.user_id()?;
+    // Folders are personal, so shared connections show up outside any folder.
+    let mut sql = String::from(
+        "SELECT c.connection_id,
+                CASE WHEN c.user_id = ac.user_id THEN c.folder_id END AS folder_id,
+                c.user_id, c.connection_name, c.host, c.port, c.db_name, c.db_user,
+                c.db_password_encrypted, c.ssl_mode, c.safety_level, c.statement_timeout_ms,
+                c.lock_timeout_ms, c.idle_in_transaction_timeout_ms, ac.permission
+         FROM connections c
+         JOIN accessible_connections ac ON ac.connection_id = c.connection_id
+         WHERE ac.user_id = ?",
+    );
 
     let has_tags = with_tags.as_ref().map(|t| !t.is_empty()).unwrap_or(false);
 
@@ -150,7 +273,7 @@ pub async fn get_connections(
         let in_clause = placeholders.join(", ");
 
         sql.push_str(&format!(
-            " AND connection_id IN (SELECT connection_id FROM connection_tags WHERE tag_id IN ({in_clause}))"
+            " AND c.connection_id IN (SELECT connection_id FROM connection_tags WHERE tag_id IN ({in_clause}))"
         ));
     }
 
@@ -166,5 +289,5 @@ pub async fn get_connections(
         }
     }
 
-    query.fetch_all(&*pool).await.map_err(|e| e.to_string())
+    query.fetch_all(&*pool).await.map_err(AppError::from)
 }
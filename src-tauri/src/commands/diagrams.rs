@@ -1,4 +1,8 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::sessions::{ensure_can_modify, ensure_owner, Owned, SessionState};
+use crate::error::AppError;
 use crate::models::diagrams::Diagram;
+use serde_json::json;
 use sqlx::SqlitePool;
 use tauri::State;
 
@@ -19,62 +23,106 @@ pub struct UpdateDiagramRequest {
 #[tauri::command]
 pub async fn create_diagram(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: CreateDiagramRequest,
-) -> Result<i64, String> {
-    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+) -> Result<i64, AppError> {
+    let user_id = session.user_id()?;
+    ensure_owner(&pool, user_id, Owned::Connection(request.connection_id)).await?;
+
+    let mut tx = pool.begin().await?;
 
     let id = sqlx::query_scalar::<_, i64>(
         "INSERT INTO diagrams (connection_id, diagram_name, definition_json) VALUES (?, ?, ?) RETURNING diagram_id",
     )
     .bind(request.connection_id)
-    .bind(request.diagram_name)
+    .bind(&request.diagram_name)
     .bind(request.definition_json)
     .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?;
+
+    tx.commit().await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "CREATE_DIAGRAM",
+        Some(request.connection_id),
+        json!({ "diagram_id": id, "diagram_name": request.diagram_name }),
+    )
+    .await;
 
-    tx.commit().await.map_err(|e| e.to_string())?;
     Ok(id)
 }
 
 #[tauri::command]
 pub async fn update_diagram(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: UpdateDiagramRequest,
-) -> Result<(), String> {
-    sqlx::query("UPDATE diagrams SET diagram_name = ?, definition_json = ? WHERE diagram_id = ?")
-        .bind(request.diagram_name)
-        .bind(request.definition_json)
-        .bind(request.diagram_id)
-        .execute(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_can_modify(&pool, user_id, Owned::Diagram(request.diagram_id)).await?;
+    let connection_id: i64 = sqlx::query_scalar(
+        "UPDATE diagrams SET diagram_name = ?, definition_json = ? WHERE diagram_id = ? RETURNING connection_id",
+    )
+    .bind(&request.diagram_name)
+    .bind(request.definition_json)
+    .bind(request.diagram_id)
+    .fetch_one(&*pool)
+    .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "UPDATE_DIAGRAM",
+        Some(connection_id),
+        json!({ "diagram_id": request.diagram_id, "diagram_name": request.diagram_name }),
+    )
+    .await;
+
     Ok(())
 }
 
 #[tauri::command]
-pub async fn delete_diagram(pool: State<'_, SqlitePool>, diagram_id: i64) -> Result<(), String> {
-    sqlx::query("DELETE FROM diagrams WHERE diagram_id = ?")
-        .bind(diagram_id)
-        .execute(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
+pub async fn delete_diagram(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    diagram_id: i64,
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_can_modify(&pool, user_id, Owned::Diagram(diagram_id)).await?;
+    let connection_id: i64 =
+        sqlx::query_scalar("DELETE FROM diagrams WHERE diagram_id = ? RETURNING connection_id")
+            .bind(diagram_id)
+            .fetch_one(&*pool)
+            .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "DELETE_DIAGRAM",
+        Some(connection_id),
+        json!({ "diagram_id": diagram_id }),
+    )
+    .await;
+
     Ok(())
 }
 
 #[tauri::command]
 pub async fn get_diagrams(
     pool: State<'_, SqlitePool>,
-    user_id: i64,
-) -> Result<Vec<Diagram>, String> {
+    session: State<'_, SessionState>,
+) -> Result<Vec<Diagram>, AppError> {
+    let user_id = session.user_id()?;
     sqlx::query_as::<_, Diagram>(
         "SELECT d.* FROM diagrams d
-         JOIN connections c ON d.connection_id = c.connection_id
+         JOIN accessible_connections c ON d.connection_id = c.connection_id
          WHERE c.user_id = ?
          ORDER BY d.created_at DESC",
     )
     .bind(user_id)
     .fetch_all(&*pool)
     .await
-    .map_err(|e| e.to_string())
+    .map_err(AppError::from)
 }
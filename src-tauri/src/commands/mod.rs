@@ -2,12 +2,26 @@ pub mod app_user_logs;
 pub mod app_users;
 pub mod bookmarks;
 pub mod connection_folders;
+pub mod connection_shares;
 pub mod connection_tags;
 pub mod connections;
 pub mod diagrams;
+pub mod pg_compare;
+pub mod pg_completion;
+pub mod pg_databases;
+pub mod pg_extensions;
+pub mod pg_monitoring;
 pub mod pg_queries;
+pub mod pg_roles;
 pub mod pinned_queries;
+pub mod pinned_query_folders;
+pub mod pinned_query_tags;
 pub mod query_history;
+pub mod query_safety;
+pub mod security_settings;
+pub mod sessions;
+pub mod sql_tools;
 pub mod tags;
+pub mod two_factor;
 
 pub mod prelude;
@@ -0,0 +1,1222 @@
+use crate::commands::pg_queries::{connect_to_connection, quote_ident};
+use crate::commands::sessions::SessionState;
+use crate::error::AppError;
+
+use serde_json::Value;
+use sqlx::{PgConnection, PgPool, SqlitePool};
+use std::collections::{BTreeMap, BTreeSet};
+use tauri::State;
+
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    MissingInTarget,
+    ExtraInTarget,
+    Changed,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct SchemaDifference {
+    pub object_type: String,
+    pub table_name: Option<String>,
+    pub object_name: String,
+    pub kind: DiffKind,
+    pub source_definition: Option<String>,
+    pub target_definition: Option<String>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct SchemaComparison {
+    pub schema_name: String,
+    pub differences: Vec<SchemaDifference>,
+    pub migration_script: Option<String>,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq)]
+struct ColumnInfo {
+    table_name: String,
+    column_name: String,
+    data_type: String,
+    not_null: bool,
+    /// The default, or the expression of a generated column.
+    column_default: Option<String>,
+    /// `pg_attribute.attidentity`: `a` (always), `d` (by default) or empty.
+    identity: String,
+    /// `pg_attribute.attgenerated`: `s` for stored generated columns, or empty.
+    generated: String,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct ConstraintInfo {
+    table_name: String,
+    constraint_name: String,
+    constraint_type: String,
+    definition: String,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct IndexInfo {
+    table_name: String,
+    index_name: String,
+    definition: String,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct ViewInfo {
+    view_name: String,
+    materialized: bool,
+    definition: String,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct FunctionInfo {
+    signature: String,
+    kind: String,
+    /// Full argument list, including OUT parameters and defaults.
+    arguments: String,
+    result: Option<String>,
+    definition: String,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq)]
+struct SequenceInfo {
+    sequence_name: String,
+    definition: String,
+    owned_by: Option<String>,
+}
+
+#[derive(Default)]
+struct SchemaSnapshot {
+    tables: BTreeMap<String, Vec<ColumnInfo>>,
+    constraints: BTreeMap<(String, String), ConstraintInfo>,
+    indexes: BTreeMap<String, IndexInfo>,
+    views: BTreeMap<String, ViewInfo>,
+    /// Views in the schema that are built on each view.
+    view_dependents: BTreeMap<String, BTreeSet<String>>,
+    functions: BTreeMap<String, FunctionInfo>,
+    sequences: BTreeMap<String, SequenceInfo>,
+}
+
+impl ColumnInfo {
+    fn definition(&self) -> String {
+        let mut definition = format!("{} {}", quote_ident(&self.column_name), self.data_type);
+        if self.not_null {
+            definition.push_str(" NOT NULL");
+        }
+        if let Some(identity) = self.identity_kind() {
+            definition.push_str(&format!(" GENERATED {identity} AS IDENTITY"));
+        } else if let Some(default) = &self.column_default {
+            if self.is_generated() {
+                definition.push_str(&format!(" GENERATED ALWAYS AS ({default}) STORED"));
+            } else {
+                definition.push_str(&format!(" DEFAULT {default}"));
+            }
+        }
+        definition
+    }
+
+    fn identity_kind(&self) -> Option<&'static str> {
+        match self.identity.as_str() {
+            "a" => Some("ALWAYS"),
+            "d" => Some("BY DEFAULT"),
+            _ => None,
+        }
+    }
+
+    fn is_generated(&self) -> bool {
+        self.generated == "s"
+    }
+
+    /// The default value, leaving out generation expressions.
+    fn plain_default(&self) -> Option<&String> {
+        self.column_default
+            .as_ref()
+            .filter(|_| !self.is_generated())
+    }
+}
+
+impl FunctionInfo {
+    fn drop_statement(&self, schema: &str) -> String {
+        format!(
+            "DROP {} {}.{};",
+            if self.kind == "p" {
+                "PROCEDURE"
+            } else {
+                "FUNCTION"
+            },
+            quote_ident(schema),
+            self.signature
+        )
+    }
+}
+
+impl SequenceInfo {
+    fn display(&self) -> String {
+        match &self.owned_by {
+            Some(owner) => format!("{} OWNED BY {owner}", self.definition),
+            None => self.definition.clone(),
+        }
+    }
+}
+
+async fn load_snapshot(pg_pool: &PgPool, schema: &str) -> Result<SchemaSnapshot, AppError> {
+    let mut snapshot = SchemaSnapshot::default();
+
+    let tables: Vec<String> = sqlx::query_scalar(
+        r#"
+    SELECT c.relname::text
+    FROM pg_class c
+    JOIN pg_namespace n ON n.oid = c.relnamespace
+    WHERE n.nspname = $1
+    AND c.relkind IN ('r', 'p')
+    "#,
+    )
+    .bind(schema)
+    .fetch_all(pg_pool)
+    .await?;
+    for table in tables {
+        snapshot.tables.insert(table, Vec::new());
+    }
+
+    let columns = sqlx::query_as::<_, ColumnInfo>(
+        r#"
+    SELECT c.relname::text AS table_name,
+       a.attname::text AS column_name,
+       format_type(a.atttypid, a.atttypmod) AS data_type,
+       a.attnotnull AS not_null,
+       pg_get_expr(d.adbin, d.adrelid) AS column_default,
+       a.attidentity::text AS identity,
+       a.attgenerated::text AS generated
+    FROM pg_attribute a
+    JOIN pg_class c ON c.oid = a.attrelid
+    JOIN pg_namespace n ON n.oid = c.relnamespace
+    LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+    WHERE n.nspname = $1
+    AND c.relkind IN ('r', 'p')
+    AND a.attnum > 0
+    AND NOT a.attisdropped
+    ORDER BY c.relname, a.attnum
+    "#,
+    )
+    .bind(schema)
+    .fetch_all(pg_pool)
+    .await?;
+    for column in columns {
+        snapshot
+            .tables
+            .entry(column.table_name.clone())
+            .or_default()
+            .push(column);
+    }
+
+    let constraints = sqlx::query_as::<_, ConstraintInfo>(
+        r#"
+    SELECT cl.relname::text AS table_name,
+       con.conname::text AS constraint_name,
+       con.contype::text AS constraint_type,
+       pg_get_constraintdef(con.oid) AS definition
+    FROM pg_constraint con
+    JOIN pg_class cl ON cl.oid = con.conrelid
+    JOIN pg_namespace n ON n.oid = cl.relnamespace
+    WHERE n.nspname = $1
+    "#,
+    )
+    .bind(schema)
+    .fetch_all(pg_pool)
+    .await?;
+    for constraint in constraints {
+        snapshot.constraints.insert(
+            (
+                constraint.table_name.clone(),
+                constraint.constraint_name.clone(),
+            ),
+            constraint,
+        );
+    }
+
+    // Indexes backing primary key, unique and exclusion constraints are
+    // covered by the constraint comparison.
+    let indexes = sqlx::query_as::<_, IndexInfo>(
+        r#"
+    SELECT t.relname::text AS table_name,
+       i.relname::text AS index_name,
+       pg_get_indexdef(i.oid) AS definition
+    FROM pg_index x
+    JOIN pg_class i ON i.oid = x.indexrelid
+    JOIN pg_class t ON t.oid = x.indrelid
+    JOIN pg_namespace n ON n.oid = i.relnamespace
+    WHERE n.nspname = $1
+    AND NOT EXISTS (
+        SELECT 1
+        FROM pg_constraint con
+        WHERE con.conindid = x.indexrelid
+        AND con.conrelid = x.indrelid
+        AND con.contype IN ('p', 'u', 'x')
+    )
+    "#,
+    )
+    .bind(schema)
+    .fetch_all(pg_pool)
+    .await?;
+    for index in indexes {
+        snapshot.indexes.insert(index.index_name.clone(), index);
+    }
+
+    let views = sqlx::query_as::<_, ViewInfo>(
+        r#"
+    SELECT c.relname::text AS view_name,
+       (c.relkind = 'm') AS materialized,
+       pg_get_viewdef(c.oid) AS definition
+    FROM pg_class c
+    JOIN pg_namespace n ON n.oid = c.relnamespace
+    WHERE n.nspname = $1
+    AND c.relkind IN ('v', 'm')
+    "#,
+    )
+    .bind(schema)
+    .fetch_all(pg_pool)
+    .await?;
+    for view in views {
+        snapshot.views.insert(view.view_name.clone(), view);
+    }
+
+    let view_dependencies: Vec<(String, String)> = sqlx::query_as(
+        r#"
+    SELECT DISTINCT referenced.relname::text, dependent.relname::text
+    FROM pg_depend d
+    JOIN pg_rewrite r ON r.oid = d.objid
+    JOIN pg_class dependent ON dependent.oid = r.ev_class
+    JOIN pg_class referenced ON referenced.oid = d.refobjid
+    JOIN pg_namespace n ON n.oid = referenced.relnamespace
+    WHERE d.classid = 'pg_rewrite'::regclass
+    AND d.refclassid = 'pg_class'::regclass
+    AND n.nspname = $1
+    AND referenced.relkind IN ('v', 'm')
+    AND dependent.relnamespace = referenced.relnamespace
+    AND dependent.oid <> referenced.oid
+    "#,
+    )
+    .bind(schema)
+    .fetch_all(pg_pool)
+    .await?;
+    for (view, dependent) in view_dependencies {
+        snapshot
+            .view_dependents
+            .entry(view)
+            .or_default()
+            .insert(dependent);
+    }
+
+    let functions = sqlx::query_as::<_, FunctionInfo>(
+        r#"
+    SELECT quote_ident(p.proname) || '(' || pg_get_function_identity_arguments(p.oid) || ')' AS signature,
+       p.prokind::text AS kind,
+       pg_get_function_arguments(p.oid) AS arguments,
+       pg_get_function_result(p.oid) AS result,
+       pg_get_functiondef(p.oid) AS definition
+    FROM pg_proc p
+    JOIN pg_namespace n ON n.oid = p.pronamespace
+    WHERE n.nspname = $1
+    AND p.prokind IN ('f', 'p')
+    AND NOT EXISTS (
+        SELECT 1 FROM pg_depend d WHERE d.objid = p.oid AND d.deptype = 'e'
+    )
+    "#,
+    )
+    .bind(schema)
+    .fetch_all(pg_pool)
+    .await?;
+    for function in functions {
+        snapshot
+            .functions
+            .insert(function.signature.clone(), function);
+    }
+
+    // Identity sequences are part of their column and are left out.
+    let sequences = sqlx::query_as::<_, SequenceInfo>(
+        r#"
+    SELECT c.relname::text AS sequence_name,
+       format('AS %s INCREMENT BY %s MINVALUE %s MAXVALUE %s START WITH %s CACHE %s %s',
+          format_type(s.seqtypid, NULL), s.seqincrement, s.seqmin, s.seqmax,
+          s.seqstart, s.seqcache, CASE WHEN s.seqcycle THEN 'CYCLE' ELSE 'NO CYCLE' END) AS definition,
+       quote_ident(t.relname) || '.' || quote_ident(a.attname) AS owned_by
+    FROM pg_sequence s
+    JOIN pg_class c ON c.oid = s.seqrelid
+    JOIN pg_namespace n ON n.oid = c.relnamespace
+    LEFT JOIN pg_depend d ON d.objid = c.oid
+        AND d.classid = 'pg_class'::regclass
+        AND d.refclassid = 'pg_class'::regclass
+        AND d.deptype IN ('a', 'i')
+    LEFT JOIN pg_class t ON t.oid = d.refobjid
+    LEFT JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid
+    WHERE n.nspname = $1
+    AND d.deptype IS DISTINCT FROM 'i'
+    "#,
+    )
+    .bind(schema)
+    .fetch_all(pg_pool)
+    .await?;
+    for sequence in sequences {
+        snapshot
+            .sequences
+            .insert(sequence.sequence_name.clone(), sequence);
+    }
+
+    Ok(snapshot)
+}
+
+async fn load_snapshot_for(
+    pool: &SqlitePool,
+    session: &SessionState,
+    connection_id: i64,
+    schema: &str,
+) -> Result<SchemaSnapshot, AppError> {
+    let (_, pg_pool) = connect_to_connection(pool, session, connection_id).await?;
+    let snapshot = load_snapshot(&pg_pool, schema).await;
+    pg_pool.close().await;
+    snapshot
+}
+
+fn diff_maps<K: Ord + Clone, V>(
+    source: &BTreeMap<K, V>,
+    target: &BTreeMap<K, V>,
+    mut describe: impl FnMut(&K, Option<&V>, Option<&V>, DiffKind),
+    same: impl Fn(&V, &V) -> bool,
+) {
+    let keys: BTreeSet<&K> = source.keys().chain(target.keys()).collect();
+    for key in keys {
+        match (source.get(key), target.get(key)) {
+            (Some(s), None) => describe(key, Some(s), None, DiffKind::MissingInTarget),
+            (None, Some(t)) => describe(key, None, Some(t), DiffKind::ExtraInTarget),
+            (Some(s), Some(t)) if !same(s, t) => describe(key, Some(s), Some(t), DiffKind::Changed),
+            _ => {}
+        }
+    }
+}
+
+fn compare_snapshots(source: &SchemaSnapshot, target: &SchemaSnapshot) -> Vec<SchemaDifference> {
+    let mut differences = Vec::new();
+
+    let table_definition = |columns: &Vec<ColumnInfo>| {
+        columns
+            .iter()
+            .map(ColumnInfo::definition)
+            .collect::<Vec<_>>()
+            .join(",\n")
+    };
+    diff_maps(
+        &source.tables,
+        &target.tables,
+        |table, s, t, kind| {
+            if kind != DiffKind::Changed {
+                differences.push(SchemaDifference {
+                    object_type: "table".to_string(),
+                    table_name: None,
+                    object_name: table.clone(),
+                    kind,
+                    source_definition: s.map(table_definition),
+                    target_definition: t.map(table_definition),
+                });
+                return;
+            }
+            let source_columns: BTreeMap<String, ColumnInfo> = s
+                .into_iter()
+                .flatten()
+                .map(|c| (c.column_name.clone(), c.clone()))
+                .collect();
+            let target_columns: BTreeMap<String, ColumnInfo> = t
+                .into_iter()
+                .flatten()
+                .map(|c| (c.column_name.clone(), c.clone()))
+                .collect();
+            diff_maps(
+                &source_columns,
+                &target_columns,
+                |column, s, t, kind| {
+                    differences.push(SchemaDifference {
+                        object_type: "column".to_string(),
+                        table_name: Some(table.clone()),
+                        object_name: column.clone(),
+                        kind,
+                        source_definition: s.map(ColumnInfo::definition),
+                        target_definition: t.map(ColumnInfo::definition),
+                    })
+                },
+                |s, t| s == t,
+            );
+        },
+        |s, t| s == t,
+    );
+
+    diff_maps(
+        &source.constraints,
+        &target.constraints,
+        |(table, name), s, t, kind| {
+            differences.push(SchemaDifference {
+                object_type: "constraint".to_string(),
+                table_name: Some(table.clone()),
+                object_name: name.clone(),
+                kind,
+                source_definition: s.map(|c| c.definition.clone()),
+                target_definition: t.map(|c| c.definition.clone()),
+            })
+        },
+        |s, t| s.definition == t.definition,
+    );
+
+    diff_maps(
+        &source.indexes,
+        &target.indexes,
+        |name, s, t, kind| {
+            differences.push(SchemaDifference {
+                object_type: "index".to_string(),
+                table_name: s.or(t).map(|i| i.table_name.clone()),
+                object_name: name.clone(),
+                kind,
+                source_definition: s.map(|i| i.definition.clone()),
+                target_definition: t.map(|i| i.definition.clone()),
+            })
+        },
+        |s, t| s.definition == t.definition,
+    );
+
+    diff_maps(
+        &source.views,
+        &target.views,
+        |name, s, t, kind| {
+            differences.push(SchemaDifference {
+                object_type: "view".to_string(),
+                table_name: None,
+                object_name: name.clone(),
+                kind,
+                source_definition: s.map(|v| v.definition.trim().to_string()),
+                target_definition: t.map(|v| v.definition.trim().to_string()),
+            })
+        },
+        |s, t| s.materialized == t.materialized && s.definition.trim() == t.definition.trim(),
+    );
+
+    diff_maps(
+        &source.functions,
+        &target.functions,
+        |signature, s, t, kind| {
+            differences.push(SchemaDifference {
+                object_type: "function".to_string(),
+                table_name: None,
+                object_name: signature.clone(),
+                kind,
+                source_definition: s.map(|f| f.definition.trim().to_string()),
+                target_definition: t.map(|f| f.definition.trim().to_string()),
+            })
+        },
+        |s, t| s.definition.trim() == t.definition.trim(),
+    );
+
+    diff_maps(
+        &source.sequences,
+        &target.sequences,
+        |name, s, t, kind| {
+            differences.push(SchemaDifference {
+                object_type: "sequence".to_string(),
+                table_name: None,
+                object_name: name.clone(),
+                kind,
+                source_definition: s.map(SequenceInfo::display),
+                target_definition: t.map(SequenceInfo::display),
+            })
+        },
+        |s, t| s == t,
+    );
+
+    differences
+}
+
+/// Orders `views` so that each one comes before the views built on it.
+fn view_order(
+    views: &BTreeSet<String>,
+    dependents: &BTreeMap<String, BTreeSet<String>>,
+) -> Vec<String> {
+    fn visit(
+        view: &String,
+        views: &BTreeSet<String>,
+        dependents: &BTreeMap<String, BTreeSet<String>>,
+        visited: &mut BTreeSet<String>,
+        order: &mut Vec<String>,
+    ) {
+        if !visited.insert(view.clone()) {
+            return;
+        }
+        for dependent in dependents.get(view).into_iter().flatten() {
+            if views.contains(dependent) {
+                visit(dependent, views, dependents, visited, order);
+            }
+        }
+        order.push(view.clone());
+    }
+
+    let mut visited = BTreeSet::new();
+    let mut order = Vec::new();
+    for view in views {
+        visit(view, views, dependents, &mut visited, &mut order);
+    }
+    order.reverse();
+    order
+}
+
+fn generate_migration_script(
+    schema: &str,
+    source: &SchemaSnapshot,
+    target: &SchemaSnapshot,
+    differences: &[SchemaDifference],
+) -> String {
+    let qualified = |name: &str| format!("{}.{}", quote_ident(schema), quote_ident(name));
+
+    let view_keyword = |view: &ViewInfo| {
+        if view.materialized {
+            "MATERIALIZED VIEW"
+        } else {
+            "VIEW"
+        }
+    };
+
+    let mut drops = Vec::new();
+    let mut table_drops = Vec::new();
+    let mut sequences = Vec::new();
+    let mut tables = Vec::new();
+    let mut constraints = Vec::new();
+    let mut foreign_keys = Vec::new();
+    let mut indexes = Vec::new();
+    let mut routines = Vec::new();
+    let mut sequence_drops = Vec::new();
+    let mut changed_views = BTreeSet::new();
+
+    for diff in differences {
+        let table = diff.table_name.as_deref().unwrap_or_default();
+        match (diff.object_type.as_str(), diff.kind) {
+            ("table", DiffKind::MissingInTarget) => {
+                let columns = source.tables[&diff.object_name]
+                    .iter()
+                    .map(|c| format!("    {}", c.definition()))
+                    .collect::<Vec<_>>()
+                    .join(",\n");
+                tables.push(format!(
+                    "CREATE TABLE {} (\n{columns}\n);",
+                    qualified(&diff.object_name)
+                ));
+            }
+            ("table", _) => {
+                // Constraints and indexes go away with the table itself.
+                table_drops.push(format!("DROP TABLE {};", qualified(&diff.object_name)));
+            }
+            ("column", kind) => {
+                let alter = format!("ALTER TABLE {}", qualified(table));
+                let column = quote_ident(&diff.object_name);
+                let source_column = source
+                    .tables
+                    .get(table)
+                    .and_then(|columns| columns.iter().find(|c| c.column_name == diff.object_name));
+                match (kind, source_column) {
+                    (DiffKind::MissingInTarget, Some(c)) => {
+                        tables.push(format!("{alter} ADD COLUMN {};", c.definition()));
+                    }
+                    (DiffKind::ExtraInTarget, _) => {
+                        tables.push(format!("{alter} DROP COLUMN {column};"));
+                    }
+                    (DiffKind::Changed, Some(c)) => {
+                        let Some(t) = target.tables.get(table).and_then(|columns| {
+                            columns.iter().find(|t| t.column_name == diff.object_name)
+                        }) else {
+                            continue;
+                        };
+                        // A generation expression cannot be added or changed in
+                        // place; the values are computed again anyway.
+                        if c.is_generated()
+                            && (!t.is_generated() || t.column_default != c.column_default)
+                        {
+                            tables.push(format!("{alter} DROP COLUMN {column};"));
+                            tables.push(format!("{alter} ADD COLUMN {};", c.definition()));
+                            continue;
+                        }
+                        if t.is_generated() {
+                            tables.push(format!("{alter} ALTER COLUMN {column} DROP EXPRESSION;"));
+                        }
+                        if t.data_type != c.data_type {
+                            tables.push(if c.is_generated() {
+                                format!("{alter} ALTER COLUMN {column} TYPE {};", c.data_type)
+                            } else {
+                                format!(
+                                    "{alter} ALTER COLUMN {column} TYPE {} USING {column}::{};",
+                                    c.data_type, c.data_type
+                                )
+                            });
+                        }
+                        if t.not_null != c.not_null {
+                            tables.push(format!(
+                                "{alter} ALTER COLUMN {column} {} NOT NULL;",
+                                if c.not_null { "SET" } else { "DROP" }
+                            ));
+                        }
+                        if t.identity_kind().is_some() && c.identity_kind().is_none() {
+                            tables.push(format!("{alter} ALTER COLUMN {column} DROP IDENTITY;"));
+                        }
+                        // Runs before ADD IDENTITY, which needs the column without a default.
+                        if !c.is_generated() && t.plain_default() != c.plain_default() {
+                            tables.push(match c.plain_default() {
+                                Some(default) => {
+                                    format!("{alter} ALTER COLUMN {column} SET DEFAULT {default};")
+                                }
+                                None => format!("{alter} ALTER COLUMN {column} DROP DEFAULT;"),
+                            });
+                        }
+                        match (t.identity_kind(), c.identity_kind()) {
+                            (None, Some(identity)) => tables.push(format!(
+                                "{alter} ALTER COLUMN {column} ADD GENERATED {identity} AS IDENTITY;"
+                            )),
+                            (Some(current), Some(identity)) if current != identity => {
+                                tables.push(format!(
+                                    "{alter} ALTER COLUMN {column} SET GENERATED {identity};"
+                                ))
+                            }
+                            _ => {}
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            ("constraint", kind) => {
+                let alter = format!("ALTER TABLE {}", qualified(table));
+                let name = quote_ident(&diff.object_name);
+                if kind != DiffKind::MissingInTarget && source.tables.contains_key(table) {
+                    drops.push(format!("{alter} DROP CONSTRAINT {name};"));
+                }
+                if let Some(constraint) = source
+                    .constraints
+                    .get(&(table.to_string(), diff.object_name.clone()))
+                {
+                    let statement =
+                        format!("{alter} ADD CONSTRAINT {name} {};", constraint.definition);
+                    if constraint.constraint_type == "f" {
+                        foreign_keys.push(statement);
+                    } else {
+                        constraints.push(statement);
+                    }
+                }
+            }
+            ("index", kind) => {
+                if kind != DiffKind::MissingInTarget && source.tables.contains_key(table) {
+                    drops.push(format!("DROP INDEX {};", qualified(&diff.object_name)));
+                }
+                if let Some(index) = source.indexes.get(&diff.object_name) {
+                    indexes.push(format!("{};", index.definition));
+                }
+            }
+            ("view", _) => {
+                changed_views.insert(diff.object_name.clone());
+            }
+            ("function", _) => {
+                let source_function = source.functions.get(&diff.object_name);
+                if let Some(function) = target.functions.get(&diff.object_name) {
+                    // CREATE OR REPLACE cannot turn a function into a procedure,
+                    // change its result type or OUT parameters, or rename arguments.
+                    if source_function.map_or(true, |s| {
+                        s.kind != function.kind
+                            || s.result != function.result
+                            || s.arguments != function.arguments
+                    }) {
+                        drops.push(function.drop_statement(schema));
+                    }
+                }
+                if let Some(function) = source_function {
+                    // pg_get_functiondef already emits CREATE OR REPLACE.
+                    routines.push(format!("{};", function.definition.trim()));
+                }
+            }
+            ("sequence", kind) => {
+                let name = qualified(&diff.object_name);
+                match source.sequences.get(&diff.object_name) {
+                    Some(sequence) => {
+                        sequences.push(if kind == DiffKind::MissingInTarget {
+                            format!("CREATE SEQUENCE {name} {};", sequence.definition)
+                        } else {
+                            format!("ALTER SEQUENCE {name} {};", sequence.definition)
+                        });
+                        // Ownership needs the column, so it is set once tables exist.
+                        if let Some(owner) = &sequence.owned_by {
+                            constraints.push(format!(
+                                "ALTER SEQUENCE {name} OWNED BY {}.{owner};",
+                                quote_ident(schema)
+                            ));
+                        } else if kind == DiffKind::Changed {
+                            constraints.push(format!("ALTER SEQUENCE {name} OWNED BY NONE;"));
+                        }
+                    }
+                    // Column defaults may still use it until the table changes
+                    // run, and dropping its owner may already have removed it.
+                    None => sequence_drops.push(format!("DROP SEQUENCE IF EXISTS {name};")),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Views built on a view that is dropped have to be dropped first and
+    // recreated afterwards, even when they match the source.
+    let mut dropped_views = BTreeSet::new();
+    let mut pending: Vec<&String> = changed_views
+        .iter()
+        .filter(|name| target.views.contains_key(*name))
+        .collect();
+    while let Some(name) = pending.pop() {
+        if dropped_views.insert(name.clone()) {
+            pending.extend(target.view_dependents.get(name).into_iter().flatten());
+        }
+    }
+    let view_drops = view_order(&dropped_views, &target.view_dependents)
+        .into_iter()
+        .rev()
+        .map(|name| {
+            let view = &target.views[&name];
+            format!("DROP {} {};", view_keyword(view), qualified(&name))
+        });
+    drops.splice(0..0, view_drops);
+
+    let created_views: BTreeSet<String> = changed_views
+        .union(&dropped_views)
+        .filter(|name| source.views.contains_key(*name))
+        .cloned()
+        .collect();
+    for name in view_order(&created_views, &source.view_dependents) {
+        let view = &source.views[&name];
+        routines.push(format!(
+            "CREATE {} {} AS\n{};",
+            view_keyword(view),
+            qualified(&name),
+            view.definition.trim().trim_end_matches(';')
+        ));
+    }
+
+    [
+        drops,
+        table_drops,
+        sequences,
+        tables,
+        constraints,
+        foreign_keys,
+        indexes,
+        routines,
+        sequence_drops,
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join("\n\n")
+}
+
+#[tauri::command]
+pub async fn compare_schemas(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    source_connection_id: i64,
+    target_connection_id: i64,
+    schema: String,
+    generate_script: Option<bool>,
+) -> Result<SchemaComparison, AppError> {
+    let (source, target) = tokio::try_join!(
+        load_snapshot_for(&pool, &session, source_connection_id, &schema),
+        load_snapshot_for(&pool, &session, target_connection_id, &schema),
+    )?;
+
+    let differences = compare_snapshots(&source, &target);
+    let migration_script = generate_script
+        .unwrap_or(false)
+        .then(|| generate_migration_script(&schema, &source, &target, &differences));
+
+    Ok(SchemaComparison {
+        schema_name: schema,
+        differences,
+        migration_script,
+    })
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct TableDataComparison {
+    pub schema_name: String,
+    pub table_name: String,
+    pub primary_key: Vec<String>,
+    pub source_row_count: i64,
+    pub target_row_count: i64,
+    pub chunks_compared: i64,
+    pub chunks_different: i64,
+    pub missing_in_target: Vec<Value>,
+    pub extra_in_target: Vec<Value>,
+    pub changed: Vec<Value>,
+    pub sync_sql: Option<String>,
+}
+
+// Row hashes are computed from the text form of each value, so both sides
+// must render dates, times, intervals and floats identically.
+const NORMALIZE_SESSION: &str = "SET TIME ZONE 'UTC'; SET DateStyle = 'ISO, YMD'; \
+    SET IntervalStyle = 'postgres'; SET extra_float_digits = 3;";
+
+async fn table_columns(
+    conn: &mut PgConnection,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<String>, AppError> {
+    sqlx::query_scalar(
+        r#"
+    SELECT a.attname::text
+    FROM pg_attribute a
+    JOIN pg_class c ON c.oid = a.attrelid
+    JOIN pg_namespace n ON n.oid = c.relnamespace
+    WHERE n.nspname = $1
+    AND c.relname = $2
+    AND a.attnum > 0
+    AND NOT a.attisdropped
+    ORDER BY a.attnum
+    "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(conn)
+    .await
+    .map_err(AppError::from)
+}
+
+async fn primary_key_columns(
+    conn: &mut PgConnection,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<String>, AppError> {
+    sqlx::query_scalar(
+        r#"
+    SELECT a.attname::text
+    FROM pg_index i
+    JOIN pg_class c ON c.oid = i.indrelid
+    JOIN pg_namespace n ON n.oid = c.relnamespace
+    JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey)
+    WHERE n.nspname = $1
+    AND c.relname = $2
+    AND i.indisprimary
+    ORDER BY array_position(i.indkey::int2[], a.attnum)
+    "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(conn)
+    .await
+    .map_err(AppError::from)
+}
+
+async fn chunk_hashes(
+    conn: &mut PgConnection,
+    hashed_rows: &str,
+    buckets: i64,
+) -> Result<BTreeMap<i64, (i64, String)>, AppError> {
+    let rows: Vec<(i64, i64, String)> = sqlx::query_as(&format!(
+        r#"
+    SELECT bucket,
+       count(*)::bigint,
+       md5(string_agg(row_hash, '' ORDER BY pk_key COLLATE "C"))
+    FROM ({hashed_rows}) s
+    GROUP BY bucket
+    "#
+    ))
+    .bind(buckets)
+    .fetch_all(conn)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(bucket, count, hash)| (bucket, (count, hash)))
+        .collect())
+}
+
+async fn row_hashes(
+    conn: &mut PgConnection,
+    hashed_rows: &str,
+    buckets: i64,
+    selected: &[i64],
+) -> Result<BTreeMap<String, String>, AppError> {
+    let rows: Vec<(String, String)> = sqlx::query_as(&format!(
+        "SELECT pk_key, row_hash FROM ({hashed_rows}) s WHERE bucket = ANY($2)"
+    ))
+    .bind(buckets)
+    .bind(selected)
+    .fetch_all(conn)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Returns each requested row's values rendered as SQL literals, keyed by primary key.
+async fn row_literals(
+    conn: &mut PgConnection,
+    relation: &str,
+    primary_key: &[String],
+    pk_key: &str,
+    columns: &[String],
+    keys: &[String],
+) -> Result<BTreeMap<String, Vec<String>>, AppError> {
+    if keys.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    let literals = columns
+        .iter()
+        .map(|c| format!("quote_nullable(t.{})", quote_ident(c)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let key_columns = |alias: &str| {
+        primary_key
+            .iter()
+            .map(|c| format!("{alias}.{}", quote_ident(c)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    // The keys are matched against the key columns themselves, converted to
+    // their types through the table's row type, so the primary key index is used.
+    let records: Vec<Value> = keys
+        .iter()
+        .filter_map(|key| serde_json::from_str::<Vec<Value>>(key).ok())
+        .map(|values| Value::Object(primary_key.iter().cloned().zip(values).collect()))
+        .collect();
+    let rows: Vec<(String, Vec<String>)> = sqlx::query_as(&format!(
+        "SELECT {pk_key}, ARRAY[{literals}]::text[] FROM {relation} t WHERE ({}) IN (SELECT {} FROM json_populate_recordset(NULL::{relation}, $1::json) k)",
+        key_columns("t"),
+        key_columns("k")
+    ))
+    .bind(Value::Array(records).to_string())
+    .fetch_all(conn)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
+fn key_condition(primary_key: &[String], literals: &[String]) -> String {
+    primary_key
+        .iter()
+        .zip(literals)
+        .map(|(column, literal)| format!("{} = {literal}", quote_ident(column)))
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+async fn compare_table_rows(
+    source: &mut PgConnection,
+    target: &mut PgConnection,
+    schema_name: String,
+    table_name: String,
+    chunk_size: i64,
+    generate_sync_sql: bool,
+) -> Result<TableDataComparison, AppError> {
+    for conn in [&mut *source, &mut *target] {
+        sqlx::raw_sql(NORMALIZE_SESSION).execute(conn).await?;
+    }
+
+    let columns = table_columns(source, &schema_name, &table_name).await?;
+    if columns.is_empty() {
+        return Err(AppError::not_found(format!(
+            "Table {schema_name}.{table_name} does not exist on the source connection"
+        )));
+    }
+    let mut source_set = columns.clone();
+    let mut target_set = table_columns(target, &schema_name, &table_name).await?;
+    source_set.sort();
+    target_set.sort();
+    if source_set != target_set {
+        return Err(AppError::validation(
+            "Table columns differ between source and target; compare the schemas first",
+        ));
+    }
+
+    let primary_key = primary_key_columns(source, &schema_name, &table_name).await?;
+    if primary_key.is_empty() {
+        return Err(AppError::validation(format!(
+            "Table {schema_name}.{table_name} has no primary key"
+        )));
+    }
+    if primary_key != primary_key_columns(target, &schema_name, &table_name).await? {
+        return Err(AppError::validation(
+            "Primary keys differ between source and target",
+        ));
+    }
+
+    let relation = format!("{}.{}", quote_ident(&schema_name), quote_ident(&table_name));
+    let pk_key = format!(
+        "json_build_array({})::text",
+        primary_key
+            .iter()
+            .map(|c| format!("t.{}", quote_ident(c)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let row_values = columns
+        .iter()
+        .map(|c| format!("t.{}", quote_ident(c)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let hashed_rows = format!(
+        "SELECT {pk_key} AS pk_key, md5(ROW({row_values})::text) AS row_hash, abs(hashtext({pk_key})::bigint) % $1 AS bucket FROM {relation} t"
+    );
+
+    let count_sql = format!("SELECT count(*) FROM {relation}");
+    let source_row_count: i64 = sqlx::query_scalar(&count_sql)
+        .fetch_one(&mut *source)
+        .await?;
+    let target_row_count: i64 = sqlx::query_scalar(&count_sql)
+        .fetch_one(&mut *target)
+        .await?;
+
+    let chunk_size = chunk_size.max(1);
+    let buckets = ((source_row_count.max(target_row_count) + chunk_size - 1) / chunk_size).max(1);
+
+    let source_chunks = chunk_hashes(source, &hashed_rows, buckets).await?;
+    let target_chunks = chunk_hashes(target, &hashed_rows, buckets).await?;
+    let different: Vec<i64> = (0..buckets)
+        .filter(|bucket| source_chunks.get(bucket) != target_chunks.get(bucket))
+        .collect();
+
+    let mut missing_keys = Vec::new();
+    let mut extra_keys = Vec::new();
+    let mut changed_keys = Vec::new();
+    if !different.is_empty() {
+        let source_rows = row_hashes(source, &hashed_rows, buckets, &different).await?;
+        let target_rows = row_hashes(target, &hashed_rows, buckets, &different).await?;
+        for (key, hash) in &source_rows {
+            match target_rows.get(key) {
+                None => missing_keys.push(key.clone()),
+                Some(target_hash) if target_hash != hash => changed_keys.push(key.clone()),
+                _ => {}
+            }
+        }
+        extra_keys.extend(
+            target_rows
+                .keys()
+                .filter(|key| !source_rows.contains_key(*key))
+                .cloned(),
+        );
+    }
+
+    let sync_sql = if generate_sync_sql {
+        let mut statements = Vec::new();
+        let column_list = columns
+            .iter()
+            .map(|c| quote_ident(c))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let upserts = [missing_keys.clone(), changed_keys.clone()].concat();
+        let source_literals =
+            row_literals(source, &relation, &primary_key, &pk_key, &columns, &upserts).await?;
+        for key in &missing_keys {
+            if let Some(values) = source_literals.get(key) {
+                statements.push(format!(
+                    "INSERT INTO {relation} ({column_list}) VALUES ({});",
+                    values.join(", ")
+                ));
+            }
+        }
+        for key in &changed_keys {
+            if let Some(values) = source_literals.get(key) {
+                let pk_values: Vec<String> = primary_key
+                    .iter()
+                    .filter_map(|pk| columns.iter().position(|c| c == pk))
+                    .map(|i| values[i].clone())
+                    .collect();
+                let assignments = columns
+                    .iter()
+                    .zip(values)
+                    .filter(|(column, _)| !primary_key.contains(column))
+                    .map(|(column, value)| format!("{} = {value}", quote_ident(column)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if !assignments.is_empty() {
+                    statements.push(format!(
+                        "UPDATE {relation} SET {assignments} WHERE {};",
+                        key_condition(&primary_key, &pk_values)
+                    ));
+                }
+            }
+        }
+
+        let target_literals = row_literals(
+            target,
+            &relation,
+            &primary_key,
+            &pk_key,
+            &primary_key,
+            &extra_keys,
+        )
+        .await?;
+        for key in &extra_keys {
+            if let Some(pk_values) = target_literals.get(key) {
+                statements.push(format!(
+                    "DELETE FROM {relation} WHERE {};",
+                    key_condition(&primary_key, pk_values)
+                ));
+            }
+        }
+
+        Some(if statements.is_empty() {
+            String::new()
+        } else {
+            format!("BEGIN;\n{}\nCOMMIT;", statements.join("\n"))
+        })
+    } else {
+        None
+    };
+
+    let to_values = |keys: Vec<String>| -> Vec<Value> {
+        keys.into_iter()
+            .map(|key| serde_json::from_str(&key).unwrap_or(Value::String(key)))
+            .collect()
+    };
+
+    Ok(TableDataComparison {
+        schema_name,
+        table_name,
+        primary_key,
+        source_row_count,
+        target_row_count,
+        chunks_compared: buckets,
+        chunks_different: different.len() as i64,
+        missing_in_target: to_values(missing_keys),
+        extra_in_target: to_values(extra_keys),
+        changed: to_values(changed_keys),
+        sync_sql,
+    })
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn compare_table_data(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    source_connection_id: i64,
+    target_connection_id: i64,
+    schema_name: String,
+    table_name: String,
+    chunk_size: Option<i64>,
+    generate_sync_sql: Option<bool>,
+) -> Result<TableDataComparison, AppError> {
+    let ((_, source_pool), (_, target_pool)) = tokio::try_join!(
+        connect_to_connection(&pool, &session, source_connection_id),
+        connect_to_connection(&pool, &session, target_connection_id),
+    )?;
+
+    let result = async {
+        let mut source = source_pool.acquire().await?;
+        let mut target = target_pool.acquire().await?;
+        compare_table_rows(
+            &mut source,
+            &mut target,
+            schema_name,
+            table_name,
+            chunk_size.unwrap_or(1000),
+            generate_sync_sql.unwrap_or(false),
+        )
+        .await
+    }
+    .await;
+
+    source_pool.close().await;
+    target_pool.close().await;
+    result
+}
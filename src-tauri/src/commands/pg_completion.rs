@@ -0,0 +1,183 @@
+use crate::commands::pg_queries::connect_to_database;
+use crate::commands::sessions::{ensure_owner, Owned, SessionState};
+use crate::error::AppError;
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, SqlitePool};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::State;
+
+#[derive(serde::Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct CompletionRelation {
+    pub schema_name: String,
+    pub relation_name: String,
+    pub relation_type: String,
+}
+
+#[derive(serde::Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct CompletionColumn {
+    pub schema_name: String,
+    pub relation_name: String,
+    pub column_name: String,
+    pub data_type: String,
+}
+
+#[derive(serde::Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct CompletionFunction {
+    pub schema_name: String,
+    pub function_name: String,
+    pub arguments: String,
+    pub return_type: Option<String>,
+    pub kind: String,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct CompletionCatalog {
+    pub schemas: Vec<String>,
+    pub relations: Vec<CompletionRelation>,
+    pub columns: Vec<CompletionColumn>,
+    pub functions: Vec<CompletionFunction>,
+    pub keywords: Vec<String>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Completion catalogs keyed by connection and browsed database.
+#[derive(Default)]
+pub struct CompletionCache(Mutex<HashMap<(i64, Option<String>), CompletionCatalog>>);
+
+impl CompletionCache {
+    fn get(&self, key: &(i64, Option<String>)) -> Option<CompletionCatalog> {
+        self.0.lock().ok().and_then(|c| c.get(key).cloned())
+    }
+
+    fn insert(&self, key: (i64, Option<String>), catalog: CompletionCatalog) {
+        if let Ok(mut catalogs) = self.0.lock() {
+            catalogs.insert(key, catalog);
+        }
+    }
+
+    pub fn invalidate(&self, connection_id: i64) {
+        if let Ok(mut catalogs) = self.0.lock() {
+            catalogs.retain(|(id, _), _| *id != connection_id);
+        }
+    }
+}
+
+async fn load_catalog(pg_pool: &PgPool) -> Result<CompletionCatalog, AppError> {
+    let schemas: Vec<String> = sqlx::query_scalar(
+        r#"
+    SELECT nspname::text
+    FROM pg_namespace
+    WHERE nspname NOT IN ('information_schema', 'pg_toast')
+    AND nspname NOT LIKE 'pg_temp_%'
+    AND nspname NOT LIKE 'pg_toast_temp_%'
+    ORDER BY nspname
+    "#,
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    let relations = sqlx::query_as::<_, CompletionRelation>(
+        r#"
+    SELECT n.nspname::text AS schema_name,
+       c.relname::text AS relation_name,
+       CASE c.relkind
+           WHEN 'r' THEN 'table'
+           WHEN 'p' THEN 'table'
+           WHEN 'v' THEN 'view'
+           WHEN 'm' THEN 'materialized_view'
+           WHEN 'f' THEN 'foreign_table'
+       END AS relation_type
+    FROM pg_class c
+    JOIN pg_namespace n ON n.oid = c.relnamespace
+    WHERE c.relkind IN ('r', 'p', 'v', 'm', 'f')
+    AND n.nspname NOT IN ('pg_catalog', 'information_schema', 'pg_toast')
+    ORDER BY n.nspname, c.relname
+    "#,
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    let columns = sqlx::query_as::<_, CompletionColumn>(
+        r#"
+    SELECT n.nspname::text AS schema_name,
+       c.relname::text AS relation_name,
+       a.attname::text AS column_name,
+       format_type(a.atttypid, a.atttypmod) AS data_type
+    FROM pg_attribute a
+    JOIN pg_class c ON c.oid = a.attrelid
+    JOIN pg_namespace n ON n.oid = c.relnamespace
+    WHERE c.relkind IN ('r', 'p', 'v', 'm', 'f')
+    AND n.nspname NOT IN ('pg_catalog', 'information_schema', 'pg_toast')
+    AND a.attnum > 0
+    AND NOT a.attisdropped
+    ORDER BY n.nspname, c.relname, a.attnum
+    "#,
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    let functions = sqlx::query_as::<_, CompletionFunction>(
+        r#"
+    SELECT n.nspname::text AS schema_name,
+       p.proname::text AS function_name,
+       pg_get_function_arguments(p.oid) AS arguments,
+       pg_get_function_result(p.oid) AS return_type,
+       CASE p.prokind
+           WHEN 'a' THEN 'aggregate'
+           WHEN 'w' THEN 'window'
+           WHEN 'p' THEN 'procedure'
+           ELSE 'function'
+       END AS kind
+    FROM pg_proc p
+    JOIN pg_namespace n ON n.oid = p.pronamespace
+    WHERE n.nspname NOT IN ('information_schema', 'pg_toast')
+    AND (n.nspname <> 'pg_catalog' OR p.proname !~ '^(_|pg_)')
+    ORDER BY n.nspname, p.proname
+    "#,
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    let keywords: Vec<String> =
+        sqlx::query_scalar("SELECT upper(word) FROM pg_get_keywords() ORDER BY word")
+            .fetch_all(pg_pool)
+            .await?;
+
+    Ok(CompletionCatalog {
+        schemas,
+        relations,
+        columns,
+        functions,
+        keywords,
+        generated_at: Utc::now(),
+    })
+}
+
+#[tauri::command]
+pub async fn get_completion_catalog(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    cache: State<'_, CompletionCache>,
+    connection_id: i64,
+    database: Option<String>,
+    force_refresh: Option<bool>,
+) -> Result<CompletionCatalog, AppError> {
+    ensure_owner(&pool, session.user_id()?, Owned::Connection(connection_id)).await?;
+    let key = (connection_id, database);
+    if !force_refresh.unwrap_or(false) {
+        if let Some(catalog) = cache.get(&key) {
+            return Ok(catalog);
+        }
+    }
+
+    let (_, pg_pool) =
+        connect_to_database(&pool, &session, connection_id, key.1.as_deref()).await?;
+    let catalog = load_catalog(&pg_pool).await;
+    pg_pool.close().await;
+    let catalog = catalog?;
+
+    cache.insert(key, catalog.clone());
+    Ok(catalog)
+}
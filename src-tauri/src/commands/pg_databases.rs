@@ -0,0 +1,332 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::pg_completion::CompletionCache;
+use crate::commands::pg_queries::{
+    connect_to_connection, connect_to_database, quote_ident, quote_literal,
+};
+use crate::commands::query_safety::{enforce_safety_level_logged, ConfirmationTokens};
+use crate::commands::sessions::{require_role, SessionState};
+use crate::error::AppError;
+use crate::models::app_users::Role;
+
+use serde_json::json;
+use sqlx::SqlitePool;
+use tauri::State;
+
+#[derive(serde::Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct PgDatabase {
+    pub database_name: String,
+    pub owner: String,
+    pub encoding: String,
+    pub collation: String,
+    pub ctype: String,
+    pub connection_limit: i32,
+    pub size_bytes: Option<i64>,
+    pub is_template: bool,
+    pub allow_connections: bool,
+    pub is_current: bool,
+}
+
+#[derive(serde::Deserialize)]
+pub struct CreateDatabaseRequest {
+    pub database_name: String,
+    pub owner: Option<String>,
+    pub template: Option<String>,
+    pub encoding: Option<String>,
+    pub lc_collate: Option<String>,
+    pub lc_ctype: Option<String>,
+    pub connection_limit: Option<i32>,
+}
+
+/// Runs `statement` under the connection's safety level, so in confirm mode it
+/// needs a token from `check_query_safety` for the same statement text.
+#[allow(clippy::too_many_arguments)]
+async fn run_admin_statement(
+    pool: &SqlitePool,
+    session: &SessionState,
+    completion_cache: &CompletionCache,
+    tokens: &ConfirmationTokens,
+    connection_id: i64,
+    database: Option<&str>,
+    statement: String,
+    action_type: &str,
+    confirmation_token: Option<&str>,
+) -> Result<(), AppError> {
+    let user_id = require_role(pool, session, Role::Editor).await?;
+    let (connection, pg_pool) = connect_to_database(pool, session, connection_id, database).await?;
+    if let Err(e) = enforce_safety_level_logged(
+        pool,
+        user_id,
+        tokens,
+        &connection,
+        &statement,
+        confirmation_token,
+    )
+    .await
+    {
+        pg_pool.close().await;
+        return Err(e);
+    }
+    // CREATE/DROP DATABASE cannot run inside a transaction block, so use the
+    // simple query protocol rather than a prepared statement.
+    let result = sqlx::raw_sql(&statement).execute(&pg_pool).await;
+    pg_pool.close().await;
+    result?;
+    // The statement changed the catalog, so cached completions are stale.
+    completion_cache.invalidate(connection_id);
+
+    let _ = log_action_internal(
+        pool,
+        user_id,
+        action_type,
+        Some(connection.connection_id),
+        json!({ "database": database, "statement": statement }),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_databases(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+) -> Result<Vec<PgDatabase>, AppError> {
+    let (_, pg_pool) = connect_to_connection(&pool, &session, connection_id).await?;
+    let databases = sqlx::query_as::<_, PgDatabase>(
+        r#"
+    SELECT d.datname::text AS database_name,
+       pg_get_userbyid(d.datdba)::text AS owner,
+       pg_encoding_to_char(d.encoding)::text AS encoding,
+       d.datcollate::text AS collation,
+       d.datctype::text AS ctype,
+       d.datconnlimit AS connection_limit,
+       CASE WHEN has_database_privilege(d.oid, 'CONNECT')
+            THEN pg_database_size(d.oid)
+       END AS size_bytes,
+       d.datistemplate AS is_template,
+       d.datallowconn AS allow_connections,
+       (d.datname = current_database()) AS is_current
+    FROM pg_database d
+    ORDER BY d.datname
+    "#,
+    )
+    .fetch_all(&pg_pool)
+    .await?;
+    pg_pool.close().await;
+    Ok(databases)
+}
+
+#[tauri::command]
+pub async fn create_database(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    completion_cache: State<'_, CompletionCache>,
+    confirmation_tokens: State<'_, ConfirmationTokens>,
+    connection_id: i64,
+    request: CreateDatabaseRequest,
+    confirmation_token: Option<String>,
+) -> Result<(), AppError> {
+    let mut statement = format!("CREATE DATABASE {}", quote_ident(&request.database_name));
+    if let Some(owner) = &request.owner {
+        statement.push_str(&format!(" OWNER {}", quote_ident(owner)));
+    }
+    if let Some(template) = &request.template {
+        statement.push_str(&format!(" TEMPLATE {}", quote_ident(template)));
+    }
+    if let Some(encoding) = &request.encoding {
+        statement.push_str(&format!(" ENCODING {}", quote_literal(encoding)));
+    }
+    if let Some(lc_collate) = &request.lc_collate {
+        statement.push_str(&format!(" LC_COLLATE {}", quote_literal(lc_collate)));
+    }
+    if let Some(lc_ctype) = &request.lc_ctype {
+        statement.push_str(&format!(" LC_CTYPE {}", quote_literal(lc_ctype)));
+    }
+    if let Some(limit) = request.connection_limit {
+        statement.push_str(&format!(" CONNECTION LIMIT {limit}"));
+    }
+
+    run_admin_statement(
+        &pool,
+        &session,
+        &completion_cache,
+        &confirmation_tokens,
+        connection_id,
+        None,
+        statement,
+        "CREATE_DATABASE",
+        confirmation_token.as_deref(),
+    )
+    .await
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn rename_database(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    completion_cache: State<'_, CompletionCache>,
+    confirmation_tokens: State<'_, ConfirmationTokens>,
+    connection_id: i64,
+    database_name: String,
+    new_name: String,
+    confirmation_token: Option<String>,
+) -> Result<(), AppError> {
+    let statement = format!(
+        "ALTER DATABASE {} RENAME TO {}",
+        quote_ident(&database_name),
+        quote_ident(&new_name)
+    );
+
+    run_admin_statement(
+        &pool,
+        &session,
+        &completion_cache,
+        &confirmation_tokens,
+        connection_id,
+        None,
+        statement,
+        "RENAME_DATABASE",
+        confirmation_token.as_deref(),
+    )
+    .await
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn drop_database(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    completion_cache: State<'_, CompletionCache>,
+    confirmation_tokens: State<'_, ConfirmationTokens>,
+    connection_id: i64,
+    database_name: String,
+    force: Option<bool>,
+    confirmation_token: Option<String>,
+) -> Result<(), AppError> {
+    let statement = format!(
+        "DROP DATABASE {}{}",
+        quote_ident(&database_name),
+        if force.unwrap_or(false) {
+            " WITH (FORCE)"
+        } else {
+            ""
+        }
+    );
+
+    run_admin_statement(
+        &pool,
+        &session,
+        &completion_cache,
+        &confirmation_tokens,
+        connection_id,
+        None,
+        statement,
+        "DROP_DATABASE",
+        confirmation_token.as_deref(),
+    )
+    .await
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_schema(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    completion_cache: State<'_, CompletionCache>,
+    confirmation_tokens: State<'_, ConfirmationTokens>,
+    connection_id: i64,
+    schema_name: String,
+    owner: Option<String>,
+    database: Option<String>,
+    confirmation_token: Option<String>,
+) -> Result<(), AppError> {
+    let mut statement = format!("CREATE SCHEMA {}", quote_ident(&schema_name));
+    if let Some(owner) = &owner {
+        statement.push_str(&format!(" AUTHORIZATION {}", quote_ident(owner)));
+    }
+
+    run_admin_statement(
+        &pool,
+        &session,
+        &completion_cache,
+        &confirmation_tokens,
+        connection_id,
+        database.as_deref(),
+        statement,
+        "CREATE_SCHEMA",
+        confirmation_token.as_deref(),
+    )
+    .await
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn rename_schema(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    completion_cache: State<'_, CompletionCache>,
+    confirmation_tokens: State<'_, ConfirmationTokens>,
+    connection_id: i64,
+    schema_name: String,
+    new_name: String,
+    database: Option<String>,
+    confirmation_token: Option<String>,
+) -> Result<(), AppError> {
+    let statement = format!(
+        "ALTER SCHEMA {} RENAME TO {}",
+        quote_ident(&schema_name),
+        quote_ident(&new_name)
+    );
+
+    run_admin_statement(
+        &pool,
+        &session,
+        &completion_cache,
+        &confirmation_tokens,
+        connection_id,
+        database.as_deref(),
+        statement,
+        "RENAME_SCHEMA",
+        confirmation_token.as_deref(),
+    )
+    .await
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn drop_schema(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    completion_cache: State<'_, CompletionCache>,
+    confirmation_tokens: State<'_, ConfirmationTokens>,
+    connection_id: i64,
+    schema_name: String,
+    cascade: Option<bool>,
+    database: Option<String>,
+    confirmation_token: Option<String>,
+) -> Result<(), AppError> {
+    let statement = format!(
+        "DROP SCHEMA {}{}",
+        quote_ident(&schema_name),
+        if cascade.unwrap_or(false) {
+            " CASCADE"
+        } else {
+            ""
+        }
+    );
+
+    run_admin_statement(
+        &pool,
+        &session,
+        &completion_cache,
+        &confirmation_tokens,
+        connection_id,
+        database.as_deref(),
+        statement,
+        "DROP_SCHEMA",
+        confirmation_token.as_deref(),
+    )
+    .await
+}
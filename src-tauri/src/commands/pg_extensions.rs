@@ -0,0 +1,205 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::pg_completion::CompletionCache;
+use crate::commands::pg_queries::{connect_to_connection, quote_ident, quote_literal};
+use crate::commands::query_safety::{enforce_safety_level_logged, ConfirmationTokens};
+use crate::commands::sessions::{require_role, SessionState};
+use crate::error::AppError;
+use crate::models::app_users::Role;
+
+use serde_json::json;
+use sqlx::SqlitePool;
+use tauri::State;
+
+#[derive(serde::Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct PgExtension {
+    pub name: String,
+    pub default_version: Option<String>,
+    pub installed_version: Option<String>,
+    pub schema_name: Option<String>,
+    pub comment: Option<String>,
+    pub update_available: bool,
+}
+
+#[derive(serde::Deserialize)]
+pub struct InstallExtensionRequest {
+    pub extension_name: String,
+    pub schema_name: Option<String>,
+    pub version: Option<String>,
+    pub cascade: Option<bool>,
+}
+
+/// Runs `statement` under the connection's safety level, so in confirm mode it
+/// needs a token from `check_query_safety` for the same statement text.
+#[allow(clippy::too_many_arguments)]
+async fn run_extension_statement(
+    pool: &SqlitePool,
+    session: &SessionState,
+    completion_cache: &CompletionCache,
+    tokens: &ConfirmationTokens,
+    connection_id: i64,
+    statement: String,
+    action_type: &str,
+    confirmation_token: Option<&str>,
+) -> Result<(), AppError> {
+    let user_id = require_role(pool, session, Role::Editor).await?;
+    let (connection, pg_pool) = connect_to_connection(pool, session, connection_id).await?;
+    if let Err(e) = enforce_safety_level_logged(
+        pool,
+        user_id,
+        tokens,
+        &connection,
+        &statement,
+        confirmation_token,
+    )
+    .await
+    {
+        pg_pool.close().await;
+        return Err(e);
+    }
+    let result = sqlx::query(&statement).execute(&pg_pool).await;
+    pg_pool.close().await;
+    result?;
+    // The statement changed the catalog, so cached completions are stale.
+    completion_cache.invalidate(connection_id);
+
+    let _ = log_action_internal(
+        pool,
+        user_id,
+        action_type,
+        Some(connection.connection_id),
+        json!({ "statement": statement }),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_extensions(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+) -> Result<Vec<PgExtension>, AppError> {
+    let (_, pg_pool) = connect_to_connection(&pool, &session, connection_id).await?;
+    let extensions = sqlx::query_as::<_, PgExtension>(
+        r#"
+    SELECT a.name::text AS name,
+       a.default_version,
+       a.installed_version,
+       n.nspname::text AS schema_name,
+       a.comment,
+       (a.installed_version IS NOT NULL
+        AND a.default_version IS DISTINCT FROM a.installed_version) AS update_available
+    FROM pg_available_extensions a
+    LEFT JOIN pg_extension e ON e.extname = a.name
+    LEFT JOIN pg_namespace n ON n.oid = e.extnamespace
+    ORDER BY (a.installed_version IS NULL), a.name
+    "#,
+    )
+    .fetch_all(&pg_pool)
+    .await?;
+    pg_pool.close().await;
+    Ok(extensions)
+}
+
+#[tauri::command]
+pub async fn install_extension(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    completion_cache: State<'_, CompletionCache>,
+    confirmation_tokens: State<'_, ConfirmationTokens>,
+    connection_id: i64,
+    request: InstallExtensionRequest,
+    confirmation_token: Option<String>,
+) -> Result<(), AppError> {
+    let mut statement = format!(
+        "CREATE EXTENSION IF NOT EXISTS {}",
+        quote_ident(&request.extension_name)
+    );
+    if let Some(schema) = &request.schema_name {
+        statement.push_str(&format!(" SCHEMA {}", quote_ident(schema)));
+    }
+    if let Some(version) = &request.version {
+        statement.push_str(&format!(" VERSION {}", quote_literal(version)));
+    }
+    if request.cascade.unwrap_or(false) {
+        statement.push_str(" CASCADE");
+    }
+
+    run_extension_statement(
+        &pool,
+        &session,
+        &completion_cache,
+        &confirmation_tokens,
+        connection_id,
+        statement,
+        "INSTALL_EXTENSION",
+        confirmation_token.as_deref(),
+    )
+    .await
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn update_extension(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    completion_cache: State<'_, CompletionCache>,
+    confirmation_tokens: State<'_, ConfirmationTokens>,
+    connection_id: i64,
+    extension_name: String,
+    version: Option<String>,
+    confirmation_token: Option<String>,
+) -> Result<(), AppError> {
+    let mut statement = format!("ALTER EXTENSION {} UPDATE", quote_ident(&extension_name));
+    if let Some(version) = &version {
+        statement.push_str(&format!(" TO {}", quote_literal(version)));
+    }
+
+    run_extension_statement(
+        &pool,
+        &session,
+        &completion_cache,
+        &confirmation_tokens,
+        connection_id,
+        statement,
+        "UPDATE_EXTENSION",
+        confirmation_token.as_deref(),
+    )
+    .await
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn drop_extension(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    completion_cache: State<'_, CompletionCache>,
+    confirmation_tokens: State<'_, ConfirmationTokens>,
+    connection_id: i64,
+    extension_name: String,
+    cascade: Option<bool>,
+    confirmation_token: Option<String>,
+) -> Result<(), AppError> {
+    let statement = format!(
+        "DROP EXTENSION {}{}",
+        quote_ident(&extension_name),
+        if cascade.unwrap_or(false) {
+            " CASCADE"
+        } else {
+            ""
+        }
+    );
+
+    run_extension_statement(
+        &pool,
+        &session,
+        &completion_cache,
+        &confirmation_tokens,
+        connection_id,
+        statement,
+        "DROP_EXTENSION",
+        confirmation_token.as_deref(),
+    )
+    .await
+}
@@ -0,0 +1,604 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::pg_queries::connect_to_connection;
+use crate::commands::pinned_queries::insert_pinned_query;
+use crate::commands::sessions::{require_role, SessionState};
+use crate::error::AppError;
+use crate::models::app_users::Role;
+
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use sqlx::{PgPool, SqlitePool};
+use tauri::State;
+
+const DEFAULT_TOP_STATEMENTS: i64 = 50;
+const MAX_TOP_STATEMENTS: i64 = 1000;
+
+#[derive(serde::Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct ServerActivity {
+    pub pid: i32,
+    pub datname: Option<String>,
+    pub usename: Option<String>,
+    pub application_name: Option<String>,
+    pub client_addr: Option<String>,
+    pub state: Option<String>,
+    pub wait_event_type: Option<String>,
+    pub wait_event: Option<String>,
+    pub query: Option<String>,
+    pub backend_start: Option<DateTime<Utc>>,
+    pub xact_start: Option<DateTime<Utc>>,
+    pub query_start: Option<DateTime<Utc>>,
+    pub query_duration_ms: Option<f64>,
+    pub blocking_pids: Vec<i32>,
+}
+
+#[tauri::command]
+pub async fn get_server_activity(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+    include_idle: Option<bool>,
+) -> Result<Vec<ServerActivity>, AppError> {
+    let (_, pg_pool) = connect_to_connection(&pool, &session, connection_id).await?;
+    let activity = sqlx::query_as::<_, ServerActivity>(
+        r#"
+    SELECT pid,
+       datname::text AS datname,
+       usename::text AS usename,
+       application_name,
+       client_addr::text AS client_addr,
+       state,
+       wait_event_type,
+       wait_event,
+       query,
+       backend_start,
+       xact_start,
+       query_start,
+       (EXTRACT(EPOCH FROM (clock_timestamp() - query_start)) * 1000)::float8 AS query_duration_ms,
+       pg_blocking_pids(pid) AS blocking_pids
+    FROM pg_stat_activity
+    WHERE pid <> pg_backend_pid()
+    AND backend_type = 'client backend'
+    AND ($1 OR state IS DISTINCT FROM 'idle')
+    ORDER BY query_start ASC NULLS LAST
+    "#,
+    )
+    .bind(include_idle.unwrap_or(false))
+    .fetch_all(&pg_pool)
+    .await?;
+    pg_pool.close().await;
+    Ok(activity)
+}
+
+#[tauri::command]
+pub async fn cancel_backend(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+    pid: i32,
+) -> Result<bool, AppError> {
+    signal_backend(
+        &pool,
+        &session,
+        connection_id,
+        pid,
+        "pg_cancel_backend",
+        "CANCEL_BACKEND",
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn terminate_backend(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+    pid: i32,
+) -> Result<bool, AppError> {
+    signal_backend(
+        &pool,
+        &session,
+        connection_id,
+        pid,
+        "pg_terminate_backend",
+        "TERMINATE_BACKEND",
+    )
+    .await
+}
+
+async fn signal_backend(
+    pool: &SqlitePool,
+    session: &SessionState,
+    connection_id: i64,
+    pid: i32,
+    function: &str,
+    action_type: &str,
+) -> Result<bool, AppError> {
+    let user_id = require_role(pool, session, Role::Editor).await?;
+    let (connection, pg_pool) = connect_to_connection(pool, session, connection_id).await?;
+    let result = sqlx::query_scalar::<_, bool>(&format!("SELECT {function}($1)"))
+        .bind(pid)
+        .fetch_one(&pg_pool)
+        .await;
+    pg_pool.close().await;
+    let signalled = result?;
+
+    let _ = log_action_internal(
+        pool,
+        user_id,
+        action_type,
+        Some(connection.connection_id),
+        json!({ "pid": pid, "signalled": signalled }),
+    )
+    .await;
+
+    Ok(signalled)
+}
+
+#[derive(serde::Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct LockEntry {
+    pub pid: i32,
+    pub locktype: String,
+    pub mode: Option<String>,
+    pub granted: bool,
+    pub relation_name: Option<String>,
+    pub wait_duration_ms: Option<f64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct LockRow {
+    pid: i32,
+    locktype: String,
+    mode: Option<String>,
+    granted: bool,
+    relation_name: Option<String>,
+    wait_duration_ms: Option<f64>,
+    usename: Option<String>,
+    application_name: Option<String>,
+    state: Option<String>,
+    query: Option<String>,
+    query_duration_ms: Option<f64>,
+    blocking_pids: Vec<i32>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct BlockingNode {
+    pub pid: i32,
+    pub usename: Option<String>,
+    pub application_name: Option<String>,
+    pub state: Option<String>,
+    pub query: Option<String>,
+    pub query_duration_ms: Option<f64>,
+    pub blocking_pids: Vec<i32>,
+    pub held_locks: Vec<LockEntry>,
+    pub waiting_locks: Vec<LockEntry>,
+    pub blocked_sessions: Vec<BlockingNode>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct LockTree {
+    pub roots: Vec<BlockingNode>,
+    pub locks: Vec<LockEntry>,
+}
+
+#[tauri::command]
+pub async fn get_lock_tree(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+) -> Result<LockTree, AppError> {
+    let (_, pg_pool) = connect_to_connection(&pool, &session, connection_id).await?;
+
+    // pg_locks.waitstart only exists since PostgreSQL 14.
+    let server_version: i32 =
+        sqlx::query_scalar("SELECT current_setting('server_version_num')::int")
+            .fetch_one(&pg_pool)
+            .await?;
+    let wait_duration = if server_version >= 140000 {
+        "(EXTRACT(EPOCH FROM (clock_timestamp() - l.waitstart)) * 1000)::float8"
+    } else {
+        "NULL::float8"
+    };
+
+    let rows = sqlx::query_as::<_, LockRow>(&format!(
+        r#"
+    WITH blocked AS (
+        SELECT pid, pg_blocking_pids(pid) AS blocking_pids
+        FROM pg_stat_activity
+        WHERE cardinality(pg_blocking_pids(pid)) > 0
+    ),
+    involved AS (
+        SELECT pid FROM blocked
+        UNION
+        SELECT unnest(blocking_pids) FROM blocked
+    )
+    SELECT l.pid,
+       l.locktype,
+       l.mode,
+       l.granted,
+       l.relation::regclass::text AS relation_name,
+       {wait_duration} AS wait_duration_ms,
+       a.usename::text AS usename,
+       a.application_name,
+       a.state,
+       a.query,
+       (EXTRACT(EPOCH FROM (clock_timestamp() - a.query_start)) * 1000)::float8 AS query_duration_ms,
+       pg_blocking_pids(l.pid) AS blocking_pids
+    FROM pg_locks l
+    JOIN pg_stat_activity a ON a.pid = l.pid
+    WHERE l.pid IN (SELECT pid FROM involved)
+    ORDER BY l.pid, l.granted DESC
+    "#
+    ))
+    .fetch_all(&pg_pool)
+    .await?;
+    pg_pool.close().await;
+
+    Ok(build_lock_tree(rows))
+}
+
+fn build_lock_tree(rows: Vec<LockRow>) -> LockTree {
+    let mut nodes: Vec<BlockingNode> = Vec::new();
+    let mut locks = Vec::with_capacity(rows.len());
+
+    for row in rows {
+        let entry = LockEntry {
+            pid: row.pid,
+            locktype: row.locktype,
+            mode: row.mode,
+            granted: row.granted,
+            relation_name: row.relation_name,
+            wait_duration_ms: row.wait_duration_ms,
+        };
+        locks.push(entry.clone());
+
+        let node = match nodes.iter_mut().find(|n| n.pid == row.pid) {
+            Some(node) => node,
+            None => {
+                nodes.push(BlockingNode {
+                    pid: row.pid,
+                    usename: row.usename,
+                    application_name: row.application_name,
+                    state: row.state,
+                    query: row.query,
+                    query_duration_ms: row.query_duration_ms,
+                    blocking_pids: row.blocking_pids,
+                    held_locks: Vec::new(),
+                    waiting_locks: Vec::new(),
+                    blocked_sessions: Vec::new(),
+                });
+                nodes.last_mut().unwrap()
+            }
+        };
+        if entry.granted {
+            node.held_locks.push(entry);
+        } else {
+            node.waiting_locks.push(entry);
+        }
+    }
+
+    let mut visited = Vec::new();
+    let roots = nodes
+        .iter()
+        .filter(|n| n.blocking_pids.is_empty())
+        .map(|n| attach_blocked_sessions(n, &nodes, &mut visited))
+        .collect();
+
+    LockTree { roots, locks }
+}
+
+fn attach_blocked_sessions(
+    node: &BlockingNode,
+    nodes: &[BlockingNode],
+    visited: &mut Vec<i32>,
+) -> BlockingNode {
+    visited.push(node.pid);
+    let mut result = node.clone();
+    for blocked in nodes.iter().filter(|n| n.blocking_pids.contains(&node.pid)) {
+        if !visited.contains(&blocked.pid) {
+            result
+                .blocked_sessions
+                .push(attach_blocked_sessions(blocked, nodes, visited));
+        }
+    }
+    result
+}
+
+#[derive(serde::Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct TableStat {
+    pub table_name: String,
+    pub total_size_bytes: i64,
+    pub table_size_bytes: i64,
+    pub indexes_size_bytes: i64,
+    pub toast_size_bytes: i64,
+    pub live_tuples: i64,
+    pub dead_tuples: i64,
+    pub dead_tuple_ratio: f64,
+    pub seq_scan: i64,
+    pub idx_scan: Option<i64>,
+    pub last_vacuum: Option<DateTime<Utc>>,
+    pub last_autovacuum: Option<DateTime<Utc>>,
+    pub last_analyze: Option<DateTime<Utc>>,
+    pub last_autoanalyze: Option<DateTime<Utc>>,
+}
+
+#[derive(serde::Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct IndexStat {
+    pub table_name: String,
+    pub index_name: String,
+    pub index_size_bytes: i64,
+    pub idx_scan: i64,
+    pub idx_tup_read: i64,
+    pub idx_tup_fetch: i64,
+    pub is_unique: bool,
+    pub is_primary: bool,
+    pub definition: String,
+    pub is_unused: bool,
+    pub duplicate_of: Option<String>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct TableStatsReport {
+    pub tables: Vec<TableStat>,
+    pub indexes: Vec<IndexStat>,
+}
+
+#[tauri::command]
+pub async fn get_table_stats(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+    schema: String,
+) -> Result<TableStatsReport, AppError> {
+    let (_, pg_pool) = connect_to_connection(&pool, &session, connection_id).await?;
+
+    let tables = sqlx::query_as::<_, TableStat>(
+        r#"
+    SELECT s.relname::text AS table_name,
+       pg_total_relation_size(s.relid) AS total_size_bytes,
+       pg_relation_size(s.relid) AS table_size_bytes,
+       pg_indexes_size(s.relid) AS indexes_size_bytes,
+       COALESCE(pg_total_relation_size(NULLIF(c.reltoastrelid, 0)), 0) AS toast_size_bytes,
+       s.n_live_tup AS live_tuples,
+       s.n_dead_tup AS dead_tuples,
+       CASE WHEN s.n_live_tup + s.n_dead_tup = 0 THEN 0
+            ELSE s.n_dead_tup::float8 / (s.n_live_tup + s.n_dead_tup)
+       END AS dead_tuple_ratio,
+       s.seq_scan,
+       s.idx_scan,
+       s.last_vacuum,
+       s.last_autovacuum,
+       s.last_analyze,
+       s.last_autoanalyze
+    FROM pg_stat_user_tables s
+    JOIN pg_class c ON c.oid = s.relid
+    WHERE s.schemaname = $1
+    ORDER BY pg_total_relation_size(s.relid) DESC
+    "#,
+    )
+    .bind(&schema)
+    .fetch_all(&pg_pool)
+    .await?;
+
+    let indexes = sqlx::query_as::<_, IndexStat>(
+        r#"
+    SELECT s.relname::text AS table_name,
+       s.indexrelname::text AS index_name,
+       pg_relation_size(s.indexrelid) AS index_size_bytes,
+       s.idx_scan,
+       s.idx_tup_read,
+       s.idx_tup_fetch,
+       i.indisunique AS is_unique,
+       i.indisprimary AS is_primary,
+       pg_get_indexdef(s.indexrelid) AS definition,
+       (s.idx_scan = 0 AND NOT i.indisunique AND NOT i.indisprimary) AS is_unused,
+       (SELECT string_agg(dc.relname, ', ' ORDER BY dc.relname)
+        FROM pg_index d
+        JOIN pg_class dc ON dc.oid = d.indexrelid
+        WHERE d.indrelid = i.indrelid
+        AND d.indexrelid <> i.indexrelid
+        AND d.indkey::text = i.indkey::text
+        AND d.indclass::text = i.indclass::text
+        AND COALESCE(pg_get_expr(d.indexprs, d.indrelid), '') = COALESCE(pg_get_expr(i.indexprs, i.indrelid), '')
+        AND COALESCE(pg_get_expr(d.indpred, d.indrelid), '') = COALESCE(pg_get_expr(i.indpred, i.indrelid), '')
+       ) AS duplicate_of
+    FROM pg_stat_user_indexes s
+    JOIN pg_index i ON i.indexrelid = s.indexrelid
+    WHERE s.schemaname = $1
+    ORDER BY s.relname, s.indexrelname
+    "#,
+    )
+    .bind(&schema)
+    .fetch_all(&pg_pool)
+    .await?;
+
+    pg_pool.close().await;
+    Ok(TableStatsReport { tables, indexes })
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum StatementOrder {
+    TotalTime,
+    MeanTime,
+    Calls,
+    Io,
+}
+
+#[derive(serde::Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct TopStatement {
+    pub queryid: Option<String>,
+    pub username: Option<String>,
+    pub query: Option<String>,
+    pub calls: i64,
+    pub total_time_ms: f64,
+    pub mean_time_ms: f64,
+    pub rows: i64,
+    pub shared_blks_hit: i64,
+    pub shared_blks_read: i64,
+    pub io_blocks: i64,
+}
+
+async fn ensure_pg_stat_statements(pg_pool: &PgPool) -> Result<(), AppError> {
+    let installed: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'pg_stat_statements')",
+    )
+    .fetch_one(pg_pool)
+    .await?;
+    if installed {
+        Ok(())
+    } else {
+        Err(AppError::validation(
+            "The pg_stat_statements extension is not installed on this database",
+        ))
+    }
+}
+
+#[tauri::command]
+pub async fn get_top_statements(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+    order_by: Option<StatementOrder>,
+    limit: Option<i64>,
+) -> Result<Vec<TopStatement>, AppError> {
+    let (_, pg_pool) = connect_to_connection(&pool, &session, connection_id).await?;
+    if let Err(e) = ensure_pg_stat_statements(&pg_pool).await {
+        pg_pool.close().await;
+        return Err(e);
+    }
+
+    // The timing columns were renamed in PostgreSQL 13.
+    let server_version: i32 =
+        sqlx::query_scalar("SELECT current_setting('server_version_num')::int")
+            .fetch_one(&pg_pool)
+            .await?;
+    let (total_col, mean_col) = if server_version >= 130000 {
+        ("s.total_exec_time", "s.mean_exec_time")
+    } else {
+        ("s.total_time", "s.mean_time")
+    };
+
+    let order_clause = match order_by.unwrap_or(StatementOrder::TotalTime) {
+        StatementOrder::TotalTime => "total_time_ms DESC",
+        StatementOrder::MeanTime => "mean_time_ms DESC",
+        StatementOrder::Calls => "calls DESC",
+        StatementOrder::Io => "io_blocks DESC",
+    };
+
+    let statements = sqlx::query_as::<_, TopStatement>(&format!(
+        r#"
+    SELECT s.queryid::text AS queryid,
+       r.rolname::text AS username,
+       s.query,
+       s.calls,
+       {total_col}::float8 AS total_time_ms,
+       {mean_col}::float8 AS mean_time_ms,
+       s.rows,
+       s.shared_blks_hit,
+       s.shared_blks_read,
+       (s.shared_blks_read + s.shared_blks_written + s.temp_blks_read + s.temp_blks_written) AS io_blocks
+    FROM pg_stat_statements s
+    LEFT JOIN pg_roles r ON r.oid = s.userid
+    WHERE s.dbid = (SELECT oid FROM pg_database WHERE datname = current_database())
+    ORDER BY {order_clause}
+    LIMIT $1
+    "#
+    ))
+    .bind(
+        limit
+            .unwrap_or(DEFAULT_TOP_STATEMENTS)
+            .clamp(1, MAX_TOP_STATEMENTS),
+    )
+    .fetch_all(&pg_pool)
+    .await?;
+
+    pg_pool.close().await;
+    Ok(statements)
+}
+
+#[tauri::command]
+pub async fn reset_top_statements(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+) -> Result<(), AppError> {
+    let user_id = require_role(&pool, &session, Role::Editor).await?;
+    let (connection, pg_pool) = connect_to_connection(&pool, &session, connection_id).await?;
+    if let Err(e) = ensure_pg_stat_statements(&pg_pool).await {
+        pg_pool.close().await;
+        return Err(e);
+    }
+
+    let result = sqlx::query("SELECT pg_stat_statements_reset()")
+        .execute(&pg_pool)
+        .await;
+    pg_pool.close().await;
+    result?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "RESET_STATEMENTS",
+        Some(connection.connection_id),
+        json!({}),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn pin_top_statement(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+    queryid: String,
+    query_name: String,
+    description: Option<String>,
+) -> Result<i64, AppError> {
+    let (_, pg_pool) = connect_to_connection(&pool, &session, connection_id).await?;
+    if let Err(e) = ensure_pg_stat_statements(&pg_pool).await {
+        pg_pool.close().await;
+        return Err(e);
+    }
+
+    let query_text: Option<String> = sqlx::query_scalar(
+        "SELECT query FROM pg_stat_statements WHERE queryid = $1::bigint LIMIT 1",
+    )
+    .bind(&queryid)
+    .fetch_optional(&pg_pool)
+    .await?
+    .flatten();
+    pg_pool.close().await;
+    let query_text =
+        query_text.ok_or_else(|| AppError::not_found(format!("Statement {queryid} not found")))?;
+
+    let user_id = session.user_id()?;
+    let id = insert_pinned_query(
+        &pool,
+        user_id,
+        Some(connection_id),
+        None,
+        &query_name,
+        &query_text,
+        description.as_deref(),
+    )
+    .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "CREATE_PINNED_QUERY",
+        Some(connection_id),
+        json!({
+            "pinned_query_id": id,
+            "query_name": query_name,
+            "query": query_text,
+            "description": description,
+            "queryid": queryid,
+        }),
+    )
+    .await;
+
+    Ok(id)
+}
@@ -1,10 +1,22 @@
 use crate::commands::app_user_logs::log_action_internal;
-use crate::models::connections::Connection;
+use crate::commands::connections::validate_timeouts;
+use crate::commands::pg_completion::CompletionCache;
+use crate::commands::query_history::store_history_result;
+use crate::commands::query_safety::{enforce_safety_level_logged, ConfirmationTokens};
+use crate::commands::sessions::{ensure_owner, user_role, Owned, SessionState};
+use crate::error::AppError;
+use crate::models::app_users::Role;
+use crate::models::connections::{Connection, SafetyLevel};
+use crate::models::query_history::ResultColumn;
 use crate::password::decrypt_data;
+use crate::sql::classifier::changes_schema;
 
 use chrono::Utc;
-use serde_json::Value;
-use sqlx::{Column, PgPool, Row, SqlitePool};
+use serde_json::{json, Value};
+use sqlx::postgres::{PgConnectOptions, PgSslMode};
+use sqlx::{Column, PgPool, Row, SqlitePool, TypeInfo};
+use std::str::FromStr;
+use std::time::Duration;
 use tauri::State;
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, sqlx::FromRow)]
@@ -38,54 +50,189 @@ pub struct ColumnDef {
     pub data_type: String,
 }
 
-fn create_pg_connection_string(connection: &Connection) -> Result<String, String> {
+/// Connection options built field by field, so no stored value or requested
+/// database name is ever parsed as part of a URL.
+fn pg_connect_options(
+    connection: &Connection,
+    database: Option<&str>,
+) -> Result<PgConnectOptions, AppError> {
     let password = decrypt_data(&connection.db_password_encrypted)
-        .map_err(|_| "Could not decrypt connection password".to_string())?;
+        .map_err(|_| AppError::internal("Could not decrypt connection password"))?;
+    let ssl_mode = PgSslMode::from_str(connection.ssl_mode.as_deref().unwrap_or("prefer"))
+        .map_err(|_| AppError::validation("Invalid SSL mode"))?;
+    let port = u16::try_from(connection.port.unwrap_or(5432))
+        .map_err(|_| AppError::validation("Invalid port"))?;
 
-    let port = connection.port.unwrap_or(5432);
-    let ssl_mode = connection.ssl_mode.as_deref().unwrap_or("prefer");
+    Ok(PgConnectOptions::new()
+        .host(&connection.host)
+        .port(port)
+        .username(&connection.db_user)
+        .password(&password)
+        .database(database.unwrap_or(&connection.db_name))
+        .ssl_mode(ssl_mode))
+}
+
+pub(crate) fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+pub(crate) fn quote_literal(value: &str) -> String {
+    if value.contains('\\') {
+        format!("E'{}'", value.replace('\\', "\\\\").replace('\'', "''"))
+    } else {
+        format!("'{}'", value.replace('\'', "''"))
+    }
+}
+
+/// Session timeouts in milliseconds; unset fields fall back to the connection's
+/// stored defaults and then to the server's.
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default)]
+pub struct QueryTimeouts {
+    pub statement_timeout_ms: Option<i64>,
+    pub lock_timeout_ms: Option<i64>,
+    pub idle_in_transaction_timeout_ms: Option<i64>,
+}
 
-    Ok(format!(
-        "postgresql://{}:{}@{}:{}/{}?sslmode={}",
-        connection.db_user, password, connection.host, port, connection.db_name, ssl_mode
-    ))
+impl QueryTimeouts {
+    fn for_connection(connection: &Connection, overrides: Option<QueryTimeouts>) -> Self {
+        let overrides = overrides.unwrap_or_default();
+        QueryTimeouts {
+            statement_timeout_ms: overrides
+                .statement_timeout_ms
+                .or(connection.statement_timeout_ms),
+            lock_timeout_ms: overrides.lock_timeout_ms.or(connection.lock_timeout_ms),
+            idle_in_transaction_timeout_ms: overrides
+                .idle_in_transaction_timeout_ms
+                .or(connection.idle_in_transaction_timeout_ms),
+        }
+    }
+
+    fn session_settings(&self) -> Vec<(&'static str, String)> {
+        [
+            ("statement_timeout", self.statement_timeout_ms),
+            ("lock_timeout", self.lock_timeout_ms),
+            (
+                "idle_in_transaction_session_timeout",
+                self.idle_in_transaction_timeout_ms,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(name, ms)| ms.map(|ms| (name, ms.to_string())))
+        .collect()
+    }
+}
+
+/// Whether `error` was raised by one of the session `timeouts` rather than by the
+/// query itself, a user cancelling it or a NOWAIT lock. Server messages may be
+/// translated, so this goes by SQLSTATE and whether the matching timeout had
+/// actually elapsed.
+fn is_timeout_error(error: &sqlx::Error, timeouts: &QueryTimeouts, elapsed: Duration) -> bool {
+    let Some(db_error) = error.as_database_error() else {
+        return false;
+    };
+    // Zero disables a timeout in Postgres.
+    let elapsed_past = |ms: Option<i64>| {
+        ms.and_then(|ms| u128::try_from(ms).ok())
+            .is_some_and(|ms| ms > 0 && elapsed.as_millis() >= ms)
+    };
+    match db_error.code().as_deref() {
+        Some("57014") => elapsed_past(timeouts.statement_timeout_ms),
+        Some("55P03") => elapsed_past(timeouts.lock_timeout_ms),
+        Some("25P03") => true,
+        _ => false,
+    }
+}
+
+pub(crate) async fn connect_to_connection(
+    pool: &SqlitePool,
+    session: &SessionState,
+    connection_id: i64,
+) -> Result<(Connection, PgPool), AppError> {
+    connect_to_database(pool, session, connection_id, None).await
+}
+
+/// Connects using the credentials of `connection_id`, optionally browsing another
+/// database on the same server instead of the one stored on the connection.
+/// Read-only connections get sessions with `default_transaction_read_only=on`.
+/// Fails unless the signed-in user owns the connection.
+pub(crate) async fn connect_to_database(
+    pool: &SqlitePool,
+    session: &SessionState,
+    connection_id: i64,
+    database: Option<&str>,
+) -> Result<(Connection, PgPool), AppError> {
+    connect_with_timeouts(pool, session, connection_id, database, None).await
+}
+
+/// Like `connect_to_database`, with per-run timeout overrides applied as session
+/// settings on top of the connection's own.
+pub(crate) async fn connect_with_timeouts(
+    pool: &SqlitePool,
+    session: &SessionState,
+    connection_id: i64,
+    database: Option<&str>,
+    timeouts: Option<QueryTimeouts>,
+) -> Result<(Connection, PgPool), AppError> {
+    let user_id = session.user_id()?;
+    if let Some(t) = timeouts {
+        validate_timeouts([
+            t.statement_timeout_ms,
+            t.lock_timeout_ms,
+            t.idle_in_transaction_timeout_ms,
+        ])?;
+    }
+    ensure_owner(pool, user_id, Owned::Connection(connection_id)).await?;
+    let mut connection =
+        sqlx::query_as::<_, Connection>("SELECT * FROM connections WHERE connection_id = ?")
+            .bind(connection_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| AppError::not_found(format!("Connection {connection_id} not found")))?;
+    // Viewers are held to read-only whatever the connection itself allows.
+    if user_role(pool, user_id).await? == Role::Viewer {
+        connection.safety_level = SafetyLevel::ReadOnly;
+    }
+    let mut options = pg_connect_options(&connection, database)?;
+    if connection.safety_level == SafetyLevel::ReadOnly {
+        options = options.options([("default_transaction_read_only", "on")]);
+    }
+    let settings = QueryTimeouts::for_connection(&connection, timeouts).session_settings();
+    if !settings.is_empty() {
+        options = options.options(settings);
+    }
+    let pg_pool = PgPool::connect_with(options).await?;
+    Ok((connection, pg_pool))
 }
 
 #[tauri::command]
 pub async fn test_connection(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     connection_id: i64,
-) -> Result<bool, String> {
+) -> Result<bool, AppError> {
+    ensure_owner(&pool, session.user_id()?, Owned::Connection(connection_id)).await?;
     let connection =
         sqlx::query_as::<_, Connection>("SELECT * FROM connections WHERE connection_id = ?")
             .bind(connection_id)
             .fetch_one(&*pool)
-            .await
-            .map_err(|e| e.to_string())?;
-    let conn_str = create_pg_connection_string(&connection)?;
-    match PgPool::connect(&conn_str).await {
+            .await?;
+    match PgPool::connect_with(pg_connect_options(&connection, None)?).await {
         Ok(pg_pool) => {
             pg_pool.close().await;
             Ok(true)
         }
-        Err(e) => Err(format!("Failed to connect: {e}")),
+        Err(e) => Err(e.into()),
     }
 }
 #[tauri::command]
 pub async fn get_schemas(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     connection_id: i64,
-) -> Result<Vec<Schema>, String> {
-    let connection =
-        sqlx::query_as::<_, Connection>("SELECT * FROM connections WHERE connection_id = ?")
-            .bind(connection_id)
-            .fetch_one(&*pool)
-            .await
-            .map_err(|e| e.to_string())?;
-    let conn_str = create_pg_connection_string(&connection)?;
-    let pg_pool = PgPool::connect(&conn_str)
-        .await
-        .map_err(|e| e.to_string())?;
+    database: Option<String>,
+) -> Result<Vec<Schema>, AppError> {
+    let (_, pg_pool) =
+        connect_to_database(&pool, &session, connection_id, database.as_deref()).await?;
     let schemas = sqlx::query_as::<_, Schema>(
         r#"
     SELECT SCHEMA_NAME
@@ -97,8 +244,7 @@ pub async fn get_schemas(
     "#,
     )
     .fetch_all(&pg_pool)
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?;
     pg_pool.close().await;
     Ok(schemas)
 }
@@ -112,39 +258,68 @@ pub struct QueryResult {
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn execute_query(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    completion_cache: State<'_, CompletionCache>,
+    confirmation_tokens: State<'_, ConfirmationTokens>,
     connection_id: i64,
     query_text: String,
-) -> Result<QueryResult, String> {
-    let connection =
-        sqlx::query_as::<_, Connection>("SELECT * FROM connections WHERE connection_id = ?")
-            .bind(connection_id)
-            .fetch_one(&*pool)
-            .await
-            .map_err(|e| format!("Failed to fetch connection: {e}"))?;
+    database: Option<String>,
+    confirmation_token: Option<String>,
+    timeouts: Option<QueryTimeouts>,
+) -> Result<QueryResult, AppError> {
+    let user_id = session.user_id()?;
+    let (connection, pg_pool) = connect_with_timeouts(
+        &pool,
+        &session,
+        connection_id,
+        database.as_deref(),
+        timeouts,
+    )
+    .await?;
 
-    let conn_str = create_pg_connection_string(&connection)?;
-    let pg_pool = PgPool::connect(&conn_str)
-        .await
-        .map_err(|e| e.to_string())?;
+    if let Err(e) = enforce_safety_level_logged(
+        &pool,
+        user_id,
+        &confirmation_tokens,
+        &connection,
+        &query_text,
+        confirmation_token.as_deref(),
+    )
+    .await
+    {
+        pg_pool.close().await;
+        return Err(e);
+    }
 
     let start = std::time::Instant::now();
 
     let result = sqlx::query(&query_text).fetch_all(&pg_pool).await;
 
-    let duration = start.elapsed().as_millis() as u64;
+    let elapsed = start.elapsed();
+    let duration = elapsed.as_millis() as u64;
     let executed_at = Utc::now().naive_utc();
 
     match result {
         Ok(pg_rows) => {
+            if changes_schema(&query_text) {
+                completion_cache.invalidate(connection_id);
+            }
+
             let mut columns = Vec::new();
+            let mut column_types = Vec::new();
             let mut rows = Vec::new();
             let row_count = pg_rows.len() as u64;
 
             if let Some(first_row) = pg_rows.first() {
                 for col in first_row.columns() {
                     columns.push(col.name().to_string());
+                    column_types.push(ResultColumn {
+                        name: col.name().to_string(),
+                        type_name: col.type_info().name().to_string(),
+                    });
                 }
             }
 
@@ -169,26 +344,30 @@ pub async fn execute_query(
                 rows.push(row_data);
             }
 
-            let _ = sqlx::query(
-                "INSERT INTO query_history (connection_id, query_text, status, execution_time_ms, executed_at) VALUES (?, ?, 'success', ?, ?)"
+            let history_id = sqlx::query_scalar::<_, i64>(
+                "INSERT INTO query_history (connection_id, query_text, status, execution_time_ms, executed_at) VALUES (?, ?, 'success', ?, ?) RETURNING history_id"
             )
             .bind(connection_id)
             .bind(&query_text)
             .bind(duration as i64)
             .bind(executed_at)
-            .execute(&*pool)
+            .fetch_one(&*pool)
             .await;
+            if let Ok(history_id) = history_id {
+                let _ = store_history_result(&pool, history_id, &column_types, &rows).await;
+            }
 
-            let short_query = if query_text.len() > 50 {
-                format!("{}...", &query_text[0..47])
-            } else {
-                query_text.clone()
-            };
             let _ = log_action_internal(
                 &pool,
-                connection.user_id,
+                user_id,
                 "EXECUTE_QUERY",
-                Some(&short_query),
+                Some(connection_id),
+                json!({
+                    "query": query_text,
+                    "database": database,
+                    "row_count": row_count,
+                    "execution_time_ms": duration,
+                }),
             )
             .await;
 
@@ -203,23 +382,41 @@ pub async fn execute_query(
         }
         Err(e) => {
             let error_msg = e.to_string();
+            let effective_timeouts = QueryTimeouts::for_connection(&connection, timeouts);
+            let status = if is_timeout_error(&e, &effective_timeouts, elapsed) {
+                "timeout"
+            } else {
+                "error"
+            };
 
             let _ = sqlx::query(
-                "INSERT INTO query_history (connection_id, query_text, status, execution_time_ms, error_message, executed_at) VALUES (?, ?, 'error', ?, ?, ?)"
+                "INSERT INTO query_history (connection_id, query_text, status, execution_time_ms, error_message, executed_at) VALUES (?, ?, ?, ?, ?, ?)"
             )
             .bind(connection_id)
             .bind(&query_text)
+            .bind(status)
             .bind(duration as i64)
             .bind(&error_msg)
             .bind(executed_at)
             .execute(&*pool)
             .await;
 
-            let _ = log_action_internal(&pool, connection.user_id, "QUERY_ERROR", Some(&error_msg))
-                .await;
+            let _ = log_action_internal(
+                &pool,
+                user_id,
+                "QUERY_ERROR",
+                Some(connection_id),
+                json!({
+                    "query": query_text,
+                    "database": database,
+                    "status": status,
+                    "error": error_msg,
+                }),
+            )
+            .await;
 
             pg_pool.close().await;
-            Err(error_msg)
+            Err(e.into())
         }
     }
 }
@@ -227,16 +424,12 @@ pub async fn execute_query(
 #[tauri::command]
 pub async fn get_tables(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     connection_id: i64,
     schema_name: String,
-) -> Result<Vec<Table>, String> {
-    let c = sqlx::query_as::<_, Connection>("SELECT * FROM connections WHERE connection_id=?")
-        .bind(connection_id)
-        .fetch_one(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
-    let s = create_pg_connection_string(&c)?;
-    let p = PgPool::connect(&s).await.map_err(|e| e.to_string())?;
+    database: Option<String>,
+) -> Result<Vec<Table>, AppError> {
+    let (_, p) = connect_to_database(&pool, &session, connection_id, database.as_deref()).await?;
     let r = sqlx::query_as::<_, Table>(
         r#"
     SELECT TABLE_NAME,
@@ -249,24 +442,19 @@ pub async fn get_tables(
     )
     .bind(schema_name)
     .fetch_all(&p)
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?;
     p.close().await;
     Ok(r)
 }
 #[tauri::command]
 pub async fn get_views(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     connection_id: i64,
     schema_name: String,
-) -> Result<Vec<View>, String> {
-    let c = sqlx::query_as::<_, Connection>("SELECT * FROM connections WHERE connection_id=?")
-        .bind(connection_id)
-        .fetch_one(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
-    let s = create_pg_connection_string(&c)?;
-    let p = PgPool::connect(&s).await.map_err(|e| e.to_string())?;
+    database: Option<String>,
+) -> Result<Vec<View>, AppError> {
+    let (_, p) = connect_to_database(&pool, &session, connection_id, database.as_deref()).await?;
     let r = sqlx::query_as::<_, View>(
         r#"
     SELECT TABLE_NAME AS view_name,
@@ -277,24 +465,19 @@ pub async fn get_views(
     )
     .bind(schema_name)
     .fetch_all(&p)
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?;
     p.close().await;
     Ok(r)
 }
 #[tauri::command]
 pub async fn get_foreign_keys(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     connection_id: i64,
     schema_name: String,
-) -> Result<Vec<ForeignKeyRelation>, String> {
-    let c = sqlx::query_as::<_, Connection>("SELECT * FROM connections WHERE connection_id=?")
-        .bind(connection_id)
-        .fetch_one(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
-    let s = create_pg_connection_string(&c)?;
-    let p = PgPool::connect(&s).await.map_err(|e| e.to_string())?;
+    database: Option<String>,
+) -> Result<Vec<ForeignKeyRelation>, AppError> {
+    let (_, p) = connect_to_database(&pool, &session, connection_id, database.as_deref()).await?;
     let r = sqlx::query_as::<_, ForeignKeyRelation>(
         r#"
     SELECT tc.constraint_name,
@@ -315,24 +498,19 @@ pub async fn get_foreign_keys(
     )
     .bind(schema_name)
     .fetch_all(&p)
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?;
     p.close().await;
     Ok(r)
 }
 #[tauri::command]
 pub async fn get_schema_columns(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     connection_id: i64,
     schema_name: String,
-) -> Result<Vec<ColumnDef>, String> {
-    let c = sqlx::query_as::<_, Connection>("SELECT * FROM connections WHERE connection_id=?")
-        .bind(connection_id)
-        .fetch_one(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
-    let s = create_pg_connection_string(&c)?;
-    let p = PgPool::connect(&s).await.map_err(|e| e.to_string())?;
+    database: Option<String>,
+) -> Result<Vec<ColumnDef>, AppError> {
+    let (_, p) = connect_to_database(&pool, &session, connection_id, database.as_deref()).await?;
     let r = sqlx::query_as::<_, ColumnDef>(
         r#"SELECT 
         table_name, 
@@ -344,8 +522,7 @@ pub async fn get_schema_columns(
     )
     .bind(schema_name)
     .fetch_all(&p)
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?;
     p.close().await;
     Ok(r)
 }
@@ -0,0 +1,556 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::pg_queries::{connect_to_connection, quote_ident, quote_literal};
+use crate::commands::query_safety::{enforce_safety_level_logged, ConfirmationTokens};
+use crate::commands::sessions::{require_role, SessionState};
+use crate::error::AppError;
+use crate::models::app_users::Role;
+
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use sqlx::{PgPool, SqlitePool};
+use tauri::State;
+
+#[derive(serde::Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct PgRole {
+    pub role_name: String,
+    pub superuser: bool,
+    pub inherit: bool,
+    pub create_role: bool,
+    pub create_db: bool,
+    pub can_login: bool,
+    pub replication: bool,
+    pub bypass_rls: bool,
+    pub connection_limit: i32,
+    /// `None` when the password never expires, including `VALID UNTIL 'infinity'`.
+    pub valid_until: Option<DateTime<Utc>>,
+    pub member_of: Vec<String>,
+    pub members: Vec<String>,
+}
+
+#[derive(serde::Deserialize, Default)]
+pub struct RoleAttributes {
+    pub superuser: Option<bool>,
+    pub inherit: Option<bool>,
+    pub create_role: Option<bool>,
+    pub create_db: Option<bool>,
+    pub can_login: Option<bool>,
+    pub replication: Option<bool>,
+    pub bypass_rls: Option<bool>,
+    pub connection_limit: Option<i32>,
+    pub password: Option<String>,
+    pub valid_until: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct CreateRoleRequest {
+    pub role_name: String,
+    pub attributes: RoleAttributes,
+    pub member_of: Option<Vec<String>>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct AlterRoleRequest {
+    pub role_name: String,
+    pub new_name: Option<String>,
+    pub attributes: RoleAttributes,
+    pub grant_membership: Option<Vec<String>>,
+    pub revoke_membership: Option<Vec<String>>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PrivilegeObjectType {
+    Schema,
+    Table,
+    Sequence,
+    Function,
+}
+
+#[derive(serde::Deserialize)]
+pub struct PrivilegeRequest {
+    pub object_type: PrivilegeObjectType,
+    pub schema_name: String,
+    /// Object name, or the argument signature for functions, e.g. `my_fn(integer, text)`.
+    /// Ignored for schemas.
+    pub object_name: Option<String>,
+    pub columns: Option<Vec<String>>,
+    pub privileges: Vec<String>,
+    pub grantee: String,
+    pub with_grant_option: Option<bool>,
+}
+
+#[derive(serde::Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct ObjectPrivilege {
+    pub object_type: String,
+    pub schema_name: String,
+    pub object_name: String,
+    pub column_name: Option<String>,
+    pub owner: String,
+    pub grantor: String,
+    pub grantee: String,
+    pub privileges: Vec<String>,
+    pub grantable_privileges: Vec<String>,
+}
+
+fn role_options(attributes: &RoleAttributes) -> String {
+    let flags = [
+        (attributes.superuser, "SUPERUSER"),
+        (attributes.inherit, "INHERIT"),
+        (attributes.create_role, "CREATEROLE"),
+        (attributes.create_db, "CREATEDB"),
+        (attributes.can_login, "LOGIN"),
+        (attributes.replication, "REPLICATION"),
+        (attributes.bypass_rls, "BYPASSRLS"),
+    ];
+
+    let mut options: Vec<String> = flags
+        .iter()
+        .filter_map(|(value, keyword)| match value {
+            Some(true) => Some(keyword.to_string()),
+            Some(false) => Some(format!("NO{keyword}")),
+            None => None,
+        })
+        .collect();
+
+    if let Some(limit) = attributes.connection_limit {
+        options.push(format!("CONNECTION LIMIT {limit}"));
+    }
+    if let Some(password) = &attributes.password {
+        options.push(format!("PASSWORD {}", quote_literal(password)));
+    }
+    if let Some(valid_until) = &attributes.valid_until {
+        options.push(format!("VALID UNTIL {}", quote_literal(valid_until)));
+    }
+
+    options.join(" ")
+}
+
+fn quote_grantee(grantee: &str) -> String {
+    if grantee.eq_ignore_ascii_case("public") {
+        "PUBLIC".to_string()
+    } else {
+        quote_ident(grantee)
+    }
+}
+
+async fn execute_statements(pg_pool: &PgPool, statements: &[String]) -> Result<(), AppError> {
+    let mut tx = pg_pool.begin().await?;
+    for statement in statements {
+        sqlx::query(statement).execute(&mut *tx).await?;
+    }
+    tx.commit().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_roles(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+) -> Result<Vec<PgRole>, AppError> {
+    let (_, pg_pool) = connect_to_connection(&pool, &session, connection_id).await?;
+    let roles = sqlx::query_as::<_, PgRole>(
+        r#"
+    SELECT r.rolname::text AS role_name,
+       r.rolsuper AS superuser,
+       r.rolinherit AS inherit,
+       r.rolcreaterole AS create_role,
+       r.rolcreatedb AS create_db,
+       r.rolcanlogin AS can_login,
+       r.rolreplication AS replication,
+       r.rolbypassrls AS bypass_rls,
+       r.rolconnlimit AS connection_limit,
+       CASE WHEN isfinite(r.rolvaliduntil) THEN r.rolvaliduntil END AS valid_until,
+       ARRAY(SELECT g.rolname::text
+             FROM pg_auth_members m
+             JOIN pg_roles g ON g.oid = m.roleid
+             WHERE m.member = r.oid
+             ORDER BY 1) AS member_of,
+       ARRAY(SELECT u.rolname::text
+             FROM pg_auth_members m
+             JOIN pg_roles u ON u.oid = m.member
+             WHERE m.roleid = r.oid
+             ORDER BY 1) AS members
+    FROM pg_roles r
+    WHERE r.rolname !~ '^pg_'
+    ORDER BY r.rolname
+    "#,
+    )
+    .fetch_all(&pg_pool)
+    .await?;
+    pg_pool.close().await;
+    Ok(roles)
+}
+
+#[tauri::command]
+pub async fn create_role(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+    request: CreateRoleRequest,
+) -> Result<(), AppError> {
+    let role = quote_ident(&request.role_name);
+    let mut statements = vec![format!(
+        "CREATE ROLE {role} {}",
+        role_options(&request.attributes)
+    )];
+    for group in request.member_of.iter().flatten() {
+        statements.push(format!("GRANT {} TO {role}", quote_ident(group)));
+    }
+
+    let user_id = require_role(&pool, &session, Role::Editor).await?;
+    let (connection, pg_pool) = connect_to_connection(&pool, &session, connection_id).await?;
+    let result = execute_statements(&pg_pool, &statements).await;
+    pg_pool.close().await;
+    result?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "CREATE_ROLE",
+        Some(connection.connection_id),
+        json!({ "role_name": request.role_name }),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn alter_role(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+    request: AlterRoleRequest,
+) -> Result<(), AppError> {
+    let mut role = quote_ident(&request.role_name);
+    let mut statements = Vec::new();
+
+    if let Some(new_name) = request
+        .new_name
+        .as_ref()
+        .filter(|n| **n != request.role_name)
+    {
+        let renamed = quote_ident(new_name);
+        statements.push(format!("ALTER ROLE {role} RENAME TO {renamed}"));
+        role = renamed;
+    }
+
+    let options = role_options(&request.attributes);
+    if !options.is_empty() {
+        statements.push(format!("ALTER ROLE {role} {options}"));
+    }
+    for group in request.grant_membership.iter().flatten() {
+        statements.push(format!("GRANT {} TO {role}", quote_ident(group)));
+    }
+    for group in request.revoke_membership.iter().flatten() {
+        statements.push(format!("REVOKE {} FROM {role}", quote_ident(group)));
+    }
+
+    if statements.is_empty() {
+        return Ok(());
+    }
+
+    let user_id = require_role(&pool, &session, Role::Editor).await?;
+    let (connection, pg_pool) = connect_to_connection(&pool, &session, connection_id).await?;
+    let result = execute_statements(&pg_pool, &statements).await;
+    pg_pool.close().await;
+    result?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "ALTER_ROLE",
+        Some(connection.connection_id),
+        json!({ "role_name": request.role_name }),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn drop_role(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    confirmation_tokens: State<'_, ConfirmationTokens>,
+    connection_id: i64,
+    role_name: String,
+    confirmation_token: Option<String>,
+) -> Result<(), AppError> {
+    let user_id = require_role(&pool, &session, Role::Editor).await?;
+    let (connection, pg_pool) = connect_to_connection(&pool, &session, connection_id).await?;
+    let statement = format!("DROP ROLE {}", quote_ident(&role_name));
+    if let Err(e) = enforce_safety_level_logged(
+        &pool,
+        user_id,
+        &confirmation_tokens,
+        &connection,
+        &statement,
+        confirmation_token.as_deref(),
+    )
+    .await
+    {
+        pg_pool.close().await;
+        return Err(e);
+    }
+    let result = sqlx::query(&statement).execute(&pg_pool).await;
+    pg_pool.close().await;
+    result?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "DROP_ROLE",
+        Some(connection.connection_id),
+        json!({ "role_name": role_name }),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_privileges(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+    schema_name: String,
+) -> Result<Vec<ObjectPrivilege>, AppError> {
+    let (_, pg_pool) = connect_to_connection(&pool, &session, connection_id).await?;
+    // A NULL ACL means the object still has its built-in default privileges,
+    // so acldefault() is used to show what is actually in effect.
+    let privileges = sqlx::query_as::<_, ObjectPrivilege>(
+        r#"
+    WITH objects AS (
+        SELECT 'schema' AS object_type,
+           n.nspname AS schema_name,
+           n.nspname AS object_name,
+           NULL::name AS column_name,
+           n.nspowner AS owner_oid,
+           COALESCE(n.nspacl, acldefault('n', n.nspowner)) AS acl
+        FROM pg_namespace n
+        WHERE n.nspname = $1
+        UNION ALL
+        SELECT CASE WHEN c.relkind = 'S' THEN 'sequence' ELSE 'table' END,
+           n.nspname,
+           c.relname,
+           NULL::name,
+           c.relowner,
+           COALESCE(c.relacl, acldefault(CASE WHEN c.relkind = 'S' THEN 's' ELSE 'r' END::"char", c.relowner))
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = $1
+        AND c.relkind IN ('r', 'p', 'v', 'm', 'f', 'S')
+        UNION ALL
+        SELECT 'column',
+           n.nspname,
+           c.relname,
+           a.attname,
+           c.relowner,
+           a.attacl
+        FROM pg_attribute a
+        JOIN pg_class c ON c.oid = a.attrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = $1
+        AND a.attacl IS NOT NULL
+        AND a.attnum > 0
+        AND NOT a.attisdropped
+        UNION ALL
+        SELECT 'function',
+           n.nspname,
+           p.oid::regprocedure::text,
+           NULL::name,
+           p.proowner,
+           COALESCE(p.proacl, acldefault('f', p.proowner))
+        FROM pg_proc p
+        JOIN pg_namespace n ON n.oid = p.pronamespace
+        WHERE n.nspname = $1
+    )
+    SELECT o.object_type,
+       o.schema_name::text AS schema_name,
+       o.object_name::text AS object_name,
+       o.column_name::text AS column_name,
+       pg_get_userbyid(o.owner_oid)::text AS owner,
+       pg_get_userbyid(acl.grantor)::text AS grantor,
+       CASE WHEN acl.grantee = 0 THEN 'PUBLIC' ELSE pg_get_userbyid(acl.grantee)::text END AS grantee,
+       array_agg(acl.privilege_type ORDER BY acl.privilege_type) AS privileges,
+       COALESCE(array_agg(acl.privilege_type ORDER BY acl.privilege_type) FILTER (WHERE acl.is_grantable), '{}') AS grantable_privileges
+    FROM objects o
+    CROSS JOIN LATERAL aclexplode(o.acl) AS acl
+    GROUP BY o.object_type, o.schema_name, o.object_name, o.column_name, o.owner_oid, acl.grantor, acl.grantee
+    ORDER BY o.object_type, o.object_name, o.column_name NULLS FIRST, grantee
+    "#,
+    )
+    .bind(schema_name)
+    .fetch_all(&pg_pool)
+    .await?;
+    pg_pool.close().await;
+    Ok(privileges)
+}
+
+async fn build_privilege_statement(
+    pg_pool: &PgPool,
+    request: &PrivilegeRequest,
+    grant: bool,
+) -> Result<String, AppError> {
+    let columns = request.columns.as_ref().filter(|c| !c.is_empty());
+    let allowed: &[&str] = match request.object_type {
+        PrivilegeObjectType::Schema => &["USAGE", "CREATE", "ALL"],
+        PrivilegeObjectType::Table if columns.is_some() => {
+            &["SELECT", "INSERT", "UPDATE", "REFERENCES", "ALL"]
+        }
+        PrivilegeObjectType::Table => &[
+            "SELECT",
+            "INSERT",
+            "UPDATE",
+            "DELETE",
+            "TRUNCATE",
+            "REFERENCES",
+            "TRIGGER",
+            "ALL",
+        ],
+        PrivilegeObjectType::Sequence => &["USAGE", "SELECT", "UPDATE", "ALL"],
+        PrivilegeObjectType::Function => &["EXECUTE", "ALL"],
+    };
+
+    if request.privileges.is_empty() {
+        return Err(AppError::validation("At least one privilege is required"));
+    }
+    let mut privileges = Vec::with_capacity(request.privileges.len());
+    for privilege in &request.privileges {
+        let privilege = privilege.trim().to_uppercase();
+        if !allowed.contains(&privilege.as_str()) {
+            return Err(AppError::validation(format!(
+                "Privilege {privilege} is not valid for {:?}",
+                request.object_type
+            )));
+        }
+        privileges.push(privilege);
+    }
+
+    let privilege_list = match columns {
+        Some(columns) => {
+            let column_list = columns
+                .iter()
+                .map(|c| quote_ident(c))
+                .collect::<Vec<_>>()
+                .join(", ");
+            privileges
+                .iter()
+                .map(|p| format!("{p} ({column_list})"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+        None => privileges.join(", "),
+    };
+
+    let object_name = || {
+        request
+            .object_name
+            .clone()
+            .ok_or_else(|| AppError::validation("An object name is required"))
+    };
+    let target = match request.object_type {
+        PrivilegeObjectType::Schema => format!("SCHEMA {}", quote_ident(&request.schema_name)),
+        PrivilegeObjectType::Table => format!(
+            "TABLE {}.{}",
+            quote_ident(&request.schema_name),
+            quote_ident(&object_name()?)
+        ),
+        PrivilegeObjectType::Sequence => format!(
+            "SEQUENCE {}.{}",
+            quote_ident(&request.schema_name),
+            quote_ident(&object_name()?)
+        ),
+        PrivilegeObjectType::Function => {
+            // Let Postgres resolve the signature so the result is safely quoted.
+            let signature: String = sqlx::query_scalar("SELECT $1::regprocedure::text")
+                .bind(format!(
+                    "{}.{}",
+                    quote_ident(&request.schema_name),
+                    object_name()?
+                ))
+                .fetch_one(pg_pool)
+                .await?;
+            format!("FUNCTION {signature}")
+        }
+    };
+
+    let grantee = quote_grantee(&request.grantee);
+    let with_grant_option = request.with_grant_option.unwrap_or(false);
+    Ok(if grant {
+        format!(
+            "GRANT {privilege_list} ON {target} TO {grantee}{}",
+            if with_grant_option {
+                " WITH GRANT OPTION"
+            } else {
+                ""
+            }
+        )
+    } else {
+        format!(
+            "REVOKE {}{privilege_list} ON {target} FROM {grantee}",
+            if with_grant_option {
+                "GRANT OPTION FOR "
+            } else {
+                ""
+            }
+        )
+    })
+}
+
+async fn change_privileges(
+    pool: &SqlitePool,
+    session: &SessionState,
+    connection_id: i64,
+    request: PrivilegeRequest,
+    grant: bool,
+) -> Result<String, AppError> {
+    let user_id = require_role(pool, session, Role::Editor).await?;
+    let (connection, pg_pool) = connect_to_connection(pool, session, connection_id).await?;
+    let result = match build_privilege_statement(&pg_pool, &request, grant).await {
+        Ok(statement) => sqlx::query(&statement)
+            .execute(&pg_pool)
+            .await
+            .map(|_| statement)
+            .map_err(AppError::from),
+        Err(e) => Err(e),
+    };
+    pg_pool.close().await;
+    let statement = result?;
+
+    let _ = log_action_internal(
+        pool,
+        user_id,
+        if grant {
+            "GRANT_PRIVILEGES"
+        } else {
+            "REVOKE_PRIVILEGES"
+        },
+        Some(connection.connection_id),
+        json!({ "statement": statement }),
+    )
+    .await;
+
+    Ok(statement)
+}
+
+#[tauri::command]
+pub async fn grant_privileges(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+    request: PrivilegeRequest,
+) -> Result<String, AppError> {
+    change_privileges(&pool, &session, connection_id, request, true).await
+}
+
+#[tauri::command]
+pub async fn revoke_privileges(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    connection_id: i64,
+    request: PrivilegeRequest,
+) -> Result<String, AppError> {
+    change_privileges(&pool, &session, connection_id, request, false).await
+}
@@ -1,10 +1,16 @@
-use crate::models::pinned_queries::PinnedQuery;
-use sqlx::SqlitePool;
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::sessions::{ensure_can_modify, ensure_owner, Owned, SessionState};
+use crate::error::AppError;
+use crate::models::pinned_queries::{PinnedQuery, PinnedQueryVersion};
+use serde_json::json;
+use sqlx::{SqliteConnection, SqlitePool};
 use tauri::State;
 
 #[derive(serde::Deserialize)]
 pub struct CreatePinnedQueryRequest {
-    pub connection_id: i64,
+    /// `None` saves the query for use against any connection.
+    pub connection_id: Option<i64>,
+    pub folder_id: Option<i64>,
     pub query_name: String,
     pub query_text: String,
     pub description: Option<String>,
@@ -13,73 +19,321 @@ pub struct CreatePinnedQueryRequest {
 #[derive(serde::Deserialize)]
 pub struct UpdatePinnedQueryRequest {
     pub pinned_query_id: i64,
+    pub connection_id: Option<i64>,
+    pub folder_id: Option<i64>,
     pub query_name: String,
+    pub query_text: String,
     pub description: Option<String>,
 }
 
+/// Inserts a pinned query owned by `user_id` together with its first version.
+pub(crate) async fn insert_pinned_query(
+    pool: &SqlitePool,
+    user_id: i64,
+    connection_id: Option<i64>,
+    folder_id: Option<i64>,
+    query_name: &str,
+    query_text: &str,
+    description: Option<&str>,
+) -> Result<i64, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO pinned_queries (user_id, connection_id, folder_id, query_name, query_text, description)
+         VALUES (?, ?, ?, ?, ?, ?) RETURNING pinned_query_id",
+    )
+    .bind(user_id)
+    .bind(connection_id)
+    .bind(folder_id)
+    .bind(query_name)
+    .bind(query_text)
+    .bind(description)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO pinned_query_versions (pinned_query_id, version_number, query_text, created_by)
+         VALUES (?, 1, ?, ?)",
+    )
+    .bind(id)
+    .bind(query_text)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(id)
+}
+
+/// Replaces a pinned query's text and records it as the next version.
+/// Returns the new version number.
+async fn save_query_text(
+    conn: &mut SqliteConnection,
+    pinned_query_id: i64,
+    query_text: &str,
+    user_id: i64,
+) -> Result<i64, AppError> {
+    sqlx::query(
+        "UPDATE pinned_queries SET query_text = ?, updated_at = CURRENT_TIMESTAMP
+         WHERE pinned_query_id = ?",
+    )
+    .bind(query_text)
+    .bind(pinned_query_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query_scalar(
+        "INSERT INTO pinned_query_versions (pinned_query_id, version_number, query_text, created_by)
+         SELECT ?1, COALESCE(MAX(version_number), 0) + 1, ?2, ?3
+         FROM pinned_query_versions WHERE pinned_query_id = ?1
+         RETURNING version_number",
+    )
+    .bind(pinned_query_id)
+    .bind(query_text)
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(AppError::from)
+}
+
 #[tauri::command]
 pub async fn create_pinned_query(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: CreatePinnedQueryRequest,
-) -> Result<i64, String> {
-    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+) -> Result<i64, AppError> {
+    let user_id = session.user_id()?;
+    if let Some(connection_id) = request.connection_id {
+        ensure_owner(&pool, user_id, Owned::Connection(connection_id)).await?;
+    }
+    if let Some(folder_id) = request.folder_id {
+        ensure_owner(&pool, user_id, Owned::PinnedQueryFolder(folder_id)).await?;
+    }
 
-    let id = sqlx::query_scalar::<_, i64>(
-        "INSERT INTO pinned_queries (connection_id, query_name, query_text, description) VALUES (?, ?, ?, ?) RETURNING pinned_query_id",
+    let id = insert_pinned_query(
+        &pool,
+        user_id,
+        request.connection_id,
+        request.folder_id,
+        &request.query_name,
+        &request.query_text,
+        request.description.as_deref(),
     )
-    .bind(request.connection_id)
-    .bind(request.query_name)
-    .bind(request.query_text)
-    .bind(request.description)
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "CREATE_PINNED_QUERY",
+        request.connection_id,
+        json!({
+            "pinned_query_id": id,
+            "query_name": request.query_name,
+            "query": request.query_text,
+            "description": request.description,
+            "folder_id": request.folder_id,
+        }),
+    )
+    .await;
 
-    tx.commit().await.map_err(|e| e.to_string())?;
     Ok(id)
 }
 
+/// Updates a pinned query. A changed text is recorded as a new version. Only
+/// the query's creator may move it to another connection or folder.
 #[tauri::command]
 pub async fn update_pinned_query(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: UpdatePinnedQueryRequest,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_can_modify(&pool, user_id, Owned::PinnedQuery(request.pinned_query_id)).await?;
+
+    let (creator, connection_id, folder_id, query_text): (i64, Option<i64>, Option<i64>, String) =
+        sqlx::query_as(
+            "SELECT user_id, connection_id, folder_id, query_text FROM pinned_queries
+             WHERE pinned_query_id = ?",
+        )
+        .bind(request.pinned_query_id)
+        .fetch_one(&*pool)
+        .await?;
+
+    let moved = request.connection_id != connection_id || request.folder_id != folder_id;
+    if moved && creator != user_id {
+        return Err(AppError::forbidden(
+            "Only the query's creator can change its connection or folder",
+        ));
+    }
+    if request.connection_id != connection_id {
+        if let Some(connection_id) = request.connection_id {
+            ensure_owner(&pool, user_id, Owned::Connection(connection_id)).await?;
+        }
+    }
+    if request.folder_id != folder_id {
+        if let Some(folder_id) = request.folder_id {
+            ensure_owner(&pool, user_id, Owned::PinnedQueryFolder(folder_id)).await?;
+        }
+    }
+
+    let mut tx = pool.begin().await?;
     sqlx::query(
-        "UPDATE pinned_queries SET query_name = ?, description = ? WHERE pinned_query_id = ?",
+        "UPDATE pinned_queries
+         SET query_name = ?, description = ?, connection_id = ?, folder_id = ?,
+             updated_at = CURRENT_TIMESTAMP
+         WHERE pinned_query_id = ?",
     )
-    .bind(request.query_name)
-    .bind(request.description)
+    .bind(&request.query_name)
+    .bind(&request.description)
+    .bind(request.connection_id)
+    .bind(request.folder_id)
     .bind(request.pinned_query_id)
-    .execute(&*pool)
-    .await
-    .map_err(|e| e.to_string())?;
+    .execute(&mut *tx)
+    .await?;
+
+    let version = if request.query_text != query_text {
+        Some(
+            save_query_text(
+                &mut tx,
+                request.pinned_query_id,
+                &request.query_text,
+                user_id,
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+    tx.commit().await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "UPDATE_PINNED_QUERY",
+        request.connection_id.or(connection_id),
+        json!({
+            "pinned_query_id": request.pinned_query_id,
+            "query_name": request.query_name,
+            "description": request.description,
+            "connection_id": request.connection_id,
+            "folder_id": request.folder_id,
+            "version": version,
+        }),
+    )
+    .await;
+
     Ok(())
 }
 
 #[tauri::command]
 pub async fn delete_pinned_query(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     pinned_query_id: i64,
-) -> Result<(), String> {
-    sqlx::query("DELETE FROM pinned_queries WHERE pinned_query_id = ?")
-        .bind(pinned_query_id)
-        .execute(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_can_modify(&pool, user_id, Owned::PinnedQuery(pinned_query_id)).await?;
+    let connection_id: Option<i64> = sqlx::query_scalar(
+        "DELETE FROM pinned_queries WHERE pinned_query_id = ? RETURNING connection_id",
+    )
+    .bind(pinned_query_id)
+    .fetch_one(&*pool)
+    .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "DELETE_PINNED_QUERY",
+        connection_id,
+        json!({ "pinned_query_id": pinned_query_id }),
+    )
+    .await;
+
     Ok(())
 }
 
+/// Every saved version of a pinned query, newest first.
+#[tauri::command]
+pub async fn get_pinned_query_versions(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    pinned_query_id: i64,
+) -> Result<Vec<PinnedQueryVersion>, AppError> {
+    ensure_owner(
+        &pool,
+        session.user_id()?,
+        Owned::PinnedQuery(pinned_query_id),
+    )
+    .await?;
+    sqlx::query_as::<_, PinnedQueryVersion>(
+        "SELECT * FROM pinned_query_versions WHERE pinned_query_id = ?
+         ORDER BY version_number DESC",
+    )
+    .bind(pinned_query_id)
+    .fetch_all(&*pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Makes an earlier version's text current again. The restored text is
+/// recorded as a new version, so history is never rewritten.
+#[tauri::command]
+pub async fn restore_pinned_query_version(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    pinned_query_id: i64,
+    version_id: i64,
+) -> Result<PinnedQuery, AppError> {
+    let user_id = session.user_id()?;
+    ensure_can_modify(&pool, user_id, Owned::PinnedQuery(pinned_query_id)).await?;
+
+    let (restored_number, restored_text): (i64, String) = sqlx::query_as(
+        "SELECT version_number, query_text FROM pinned_query_versions
+         WHERE version_id = ? AND pinned_query_id = ?",
+    )
+    .bind(version_id)
+    .bind(pinned_query_id)
+    .fetch_optional(&*pool)
+    .await?
+    .ok_or_else(|| AppError::not_found(format!("Version {version_id} not found")))?;
+
+    let mut tx = pool.begin().await?;
+    let version = save_query_text(&mut tx, pinned_query_id, &restored_text, user_id).await?;
+    let pinned_query =
+        sqlx::query_as::<_, PinnedQuery>("SELECT * FROM pinned_queries WHERE pinned_query_id = ?")
+            .bind(pinned_query_id)
+            .fetch_one(&mut *tx)
+            .await?;
+    tx.commit().await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "RESTORE_PINNED_QUERY_VERSION",
+        pinned_query.connection_id,
+        json!({
+            "pinned_query_id": pinned_query_id,
+            "restored_version": restored_number,
+            "version": version,
+        }),
+    )
+    .await;
+
+    Ok(pinned_query)
+}
+
 #[tauri::command]
 pub async fn get_pinned_queries(
     pool: State<'_, SqlitePool>,
-    user_id: i64,
+    session: State<'_, SessionState>,
     search_query: Option<String>,
     sort_asc: Option<bool>,
-) -> Result<Vec<PinnedQuery>, String> {
+) -> Result<Vec<PinnedQuery>, AppError> {
+    let user_id = session.user_id()?;
     let mut sql = String::from(
         "SELECT pq.* FROM pinned_queries pq
-         JOIN connections c ON pq.connection_id = c.connection_id
-         WHERE c.user_id = ?",
+         WHERE pq.user_id = ?1
+            OR pq.connection_id IN (SELECT connection_id FROM accessible_connections WHERE user_id = ?1)",
     );
 
     let mut order_clauses = Vec::new();
@@ -90,7 +344,7 @@ pub async fn get_pinned_queries(
         .unwrap_or(false);
 
     if has_search {
-        order_clauses.push("(pq.query_name LIKE ?) DESC");
+        order_clauses.push("(pq.query_name LIKE ?2) DESC");
     }
 
     let is_asc = sort_asc.unwrap_or(true);
@@ -114,5 +368,5 @@ pub async fn get_pinned_queries(
         }
     }
 
-    query.fetch_all(&*pool).await.map_err(|e| e.to_string())
+    query.fetch_all(&*pool).await.map_err(AppError::from)
 }
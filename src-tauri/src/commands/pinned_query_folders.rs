@@ -0,0 +1,112 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::sessions::{ensure_owner, Owned, SessionState};
+use crate::error::AppError;
+use crate::models::pinned_query_folders::PinnedQueryFolder;
+use serde_json::json;
+use sqlx::SqlitePool;
+use tauri::State;
+
+#[derive(serde::Deserialize)]
+pub struct CreatePinnedQueryFolderRequest {
+    pub folder_name: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct UpdatePinnedQueryFolderRequest {
+    pub folder_id: i64,
+    pub folder_name: String,
+}
+
+#[tauri::command]
+pub async fn create_pinned_query_folder(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    request: CreatePinnedQueryFolderRequest,
+) -> Result<i64, AppError> {
+    let user_id = session.user_id()?;
+    let id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO pinned_query_folders (user_id, folder_name) VALUES (?, ?) RETURNING folder_id",
+    )
+    .bind(user_id)
+    .bind(&request.folder_name)
+    .fetch_one(&*pool)
+    .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "CREATE_PINNED_QUERY_FOLDER",
+        None,
+        json!({ "folder_id": id, "folder_name": request.folder_name }),
+    )
+    .await;
+
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn update_pinned_query_folder(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    request: UpdatePinnedQueryFolderRequest,
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_owner(&pool, user_id, Owned::PinnedQueryFolder(request.folder_id)).await?;
+    sqlx::query("UPDATE pinned_query_folders SET folder_name = ? WHERE folder_id = ?")
+        .bind(&request.folder_name)
+        .bind(request.folder_id)
+        .execute(&*pool)
+        .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "UPDATE_PINNED_QUERY_FOLDER",
+        None,
+        json!({ "folder_id": request.folder_id, "folder_name": request.folder_name }),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Deletes a folder. Its queries are kept and move back to the top level.
+#[tauri::command]
+pub async fn delete_pinned_query_folder(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    folder_id: i64,
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_owner(&pool, user_id, Owned::PinnedQueryFolder(folder_id)).await?;
+    sqlx::query("DELETE FROM pinned_query_folders WHERE folder_id = ?")
+        .bind(folder_id)
+        .execute(&*pool)
+        .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "DELETE_PINNED_QUERY_FOLDER",
+        None,
+        json!({ "folder_id": folder_id }),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_pinned_query_folders(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+) -> Result<Vec<PinnedQueryFolder>, AppError> {
+    let user_id = session.user_id()?;
+    sqlx::query_as::<_, PinnedQueryFolder>(
+        "SELECT * FROM pinned_query_folders WHERE user_id = ? ORDER BY folder_name",
+    )
+    .bind(user_id)
+    .fetch_all(&*pool)
+    .await
+    .map_err(AppError::from)
+}
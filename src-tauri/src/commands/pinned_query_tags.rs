@@ -0,0 +1,87 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::sessions::{ensure_owner, Owned, SessionState};
+use crate::error::AppError;
+use crate::models::pinned_query_tags::PinnedQueryTag;
+use serde_json::json;
+use sqlx::SqlitePool;
+use tauri::State;
+
+#[derive(serde::Deserialize)]
+pub struct AddPinnedQueryTagRequest {
+    pub tag_id: i64,
+    pub pinned_query_id: i64,
+}
+
+#[tauri::command]
+pub async fn add_pinned_query_tag(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    request: AddPinnedQueryTagRequest,
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_owner(&pool, user_id, Owned::Tag(request.tag_id)).await?;
+    ensure_owner(&pool, user_id, Owned::PinnedQuery(request.pinned_query_id)).await?;
+
+    sqlx::query("INSERT OR IGNORE INTO pinned_query_tags (tag_id, pinned_query_id) VALUES (?, ?)")
+        .bind(request.tag_id)
+        .bind(request.pinned_query_id)
+        .execute(&*pool)
+        .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "ADD_PINNED_QUERY_TAG",
+        None,
+        json!({ "tag_id": request.tag_id, "pinned_query_id": request.pinned_query_id }),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_pinned_query_tag(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    tag_id: i64,
+    pinned_query_id: i64,
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_owner(&pool, user_id, Owned::Tag(tag_id)).await?;
+    ensure_owner(&pool, user_id, Owned::PinnedQuery(pinned_query_id)).await?;
+
+    sqlx::query("DELETE FROM pinned_query_tags WHERE tag_id = ? AND pinned_query_id = ?")
+        .bind(tag_id)
+        .bind(pinned_query_id)
+        .execute(&*pool)
+        .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "REMOVE_PINNED_QUERY_TAG",
+        None,
+        json!({ "tag_id": tag_id, "pinned_query_id": pinned_query_id }),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_all_pinned_query_tags(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+) -> Result<Vec<PinnedQueryTag>, AppError> {
+    // Tags are personal; other users' tags on a shared query stay hidden.
+    sqlx::query_as::<_, PinnedQueryTag>(
+        "SELECT pt.* FROM pinned_query_tags pt
+         JOIN tags t ON pt.tag_id = t.tag_id
+         WHERE t.user_id = ?",
+    )
+    .bind(session.user_id()?)
+    .fetch_all(&*pool)
+    .await
+    .map_err(AppError::from)
+}
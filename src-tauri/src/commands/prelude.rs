@@ -4,10 +4,24 @@ pub use app_user_logs::*;
 pub use app_users::*;
 pub use bookmarks::*;
 pub use connection_folders::*;
+pub use connection_shares::*;
 pub use connection_tags::*;
 pub use connections::*;
 pub use diagrams::*;
+pub use pg_compare::*;
+pub use pg_completion::*;
+pub use pg_databases::*;
+pub use pg_extensions::*;
+pub use pg_monitoring::*;
 pub use pg_queries::*;
+pub use pg_roles::*;
 pub use pinned_queries::*;
+pub use pinned_query_folders::*;
+pub use pinned_query_tags::*;
 pub use query_history::*;
+pub use query_safety::*;
+pub use security_settings::*;
+pub use sessions::*;
+pub use sql_tools::*;
 pub use tags::*;
+pub use two_factor::*;
@@ -1,7 +1,31 @@
-use crate::models::query_history::QueryHistory;
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::sessions::{
+    ensure_connection_permission, ensure_owner, require_role, Owned, SessionState,
+};
+use crate::error::AppError;
+use crate::models::app_users::Role;
+use crate::models::connection_shares::SharePermission;
+use crate::models::query_history::{
+    HistoryResult, HistoryResultSettings, HistoryRetention, QueryHistory, ResultColumn,
+};
+use chrono::{Days, NaiveDate, NaiveDateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::{json, Value};
 use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::time::Duration;
 use tauri::State;
 
+/// How often the retention policy is applied in the background.
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const DEFAULT_STATISTICS_DAYS: u64 = 30;
+const MAX_STATISTICS_DAYS: i64 = 366;
+const DEFAULT_TOP_QUERIES: i64 = 10;
+
 #[derive(serde::Deserialize)]
 pub struct AddQueryHistoryRequest {
     pub connection_id: i64,
@@ -13,7 +37,6 @@ pub struct AddQueryHistoryRequest {
 
 #[derive(serde::Deserialize)]
 pub struct GetQueryHistoryRequest {
-    pub user_id: i64,
     pub limit: i64,
     pub offset: i64,
     pub search_query: Option<String>,
@@ -21,14 +44,25 @@ pub struct GetQueryHistoryRequest {
     pub start_date: Option<String>,
     pub end_date: Option<String>,
     pub sort_desc: Option<bool>,
+    pub connection_id: Option<i64>,
+    /// Returns one entry per distinct query and connection, with `run_count`.
+    pub collapse_duplicates: Option<bool>,
 }
 
 #[tauri::command]
 pub async fn add_query_history(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: AddQueryHistoryRequest,
-) -> Result<i64, String> {
-    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+) -> Result<i64, AppError> {
+    ensure_owner(
+        &pool,
+        session.user_id()?,
+        Owned::Connection(request.connection_id),
+    )
+    .await?;
+
+    let mut tx = pool.begin().await?;
 
     let id = sqlx::query_scalar::<_, i64>(
         "INSERT INTO query_history (connection_id, query_text, status, execution_time_ms, error_message) VALUES (?, ?, ?, ?, ?) RETURNING history_id",
@@ -39,23 +73,153 @@ pub async fn add_query_history(
     .bind(request.execution_time_ms)
     .bind(request.error_message)
     .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?;
 
-    tx.commit().await.map_err(|e| e.to_string())?;
+    tx.commit().await?;
     Ok(id)
 }
 
+#[derive(serde::Deserialize)]
+pub struct ConnectionStatisticsRequest {
+    pub connection_id: i64,
+    /// Defaults to `DEFAULT_STATISTICS_DAYS` before `to`.
+    pub from: Option<NaiveDateTime>,
+    /// Defaults to now.
+    pub to: Option<NaiveDateTime>,
+    /// Length of the most frequent and slowest query lists.
+    pub top_n: Option<i64>,
+}
+
+#[derive(Default, serde::Serialize)]
+pub struct ExecutionStats {
+    pub total: i64,
+    pub success: i64,
+    pub error: i64,
+    pub timeout: i64,
+    /// Share of runs that succeeded, 0 to 1; `None` without runs.
+    pub success_ratio: Option<f64>,
+    pub avg_execution_ms: Option<f64>,
+    pub p95_execution_ms: Option<i64>,
+}
+
+#[derive(serde::Serialize)]
+pub struct DailyExecutionStats {
+    pub day: NaiveDate,
+    #[serde(flatten)]
+    pub stats: ExecutionStats,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow)]
+pub struct FrequentQuery {
+    pub query_text: String,
+    pub run_count: i64,
+    pub avg_execution_ms: Option<f64>,
+    pub last_executed_at: Option<NaiveDateTime>,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow)]
+pub struct SlowQuery {
+    pub history_id: i64,
+    pub query_text: String,
+    pub execution_time_ms: i64,
+    pub executed_at: Option<NaiveDateTime>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ConnectionStatistics {
+    pub connection_id: i64,
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+    pub overall: ExecutionStats,
+    /// One entry per day in the range, including days without runs.
+    pub daily: Vec<DailyExecutionStats>,
+    pub most_frequent: Vec<FrequentQuery>,
+    pub slowest: Vec<SlowQuery>,
+}
+
+/// How a history search is matched against the query text.
+enum HistorySearch {
+    /// FTS5 query requiring every word as a prefix.
+    Words(String),
+    /// LIKE pattern matching the search text anywhere in the query.
+    Substring(String),
+}
+
+/// Turns free text into an FTS5 query requiring every word as a prefix, so
+/// user input is never parsed as FTS syntax.
+fn fts_query(search: &str) -> Option<String> {
+    let terms: Vec<String> = search
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+fn like_pattern(search: &str) -> String {
+    let escaped = search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
 #[tauri::command]
 pub async fn get_query_history(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: GetQueryHistoryRequest,
-) -> Result<Vec<QueryHistory>, String> {
-    let mut sql = String::from(
-        "SELECT qh.* FROM query_history qh
-         JOIN connections c ON qh.connection_id = c.connection_id
-         WHERE c.user_id = ?",
-    );
+) -> Result<Vec<QueryHistory>, AppError> {
+    let user_id = session.user_id()?;
+    let search = request
+        .search_query
+        .as_deref()
+        .and_then(|text| Some((text, fts_query(text)?)));
+    let Some((text, words)) = search else {
+        return fetch_query_history(&pool, user_id, &request, None, request.offset).await;
+    };
+
+    let words = HistorySearch::Words(words);
+    let entries =
+        fetch_query_history(&pool, user_id, &request, Some(&words), request.offset).await?;
+    if !entries.is_empty() {
+        return Ok(entries);
+    }
+    // The word index only matches from the start of a word, so when it finds
+    // nothing at all, search for the text anywhere in the query instead.
+    if request.offset > 0
+        && !fetch_query_history(&pool, user_id, &request, Some(&words), 0)
+            .await?
+            .is_empty()
+    {
+        return Ok(entries);
+    }
+    let substring = HistorySearch::Substring(like_pattern(text.trim()));
+    fetch_query_history(&pool, user_id, &request, Some(&substring), request.offset).await
+}
+
+async fn fetch_query_history(
+    pool: &SqlitePool,
+    user_id: i64,
+    request: &GetQueryHistoryRequest,
+    search: Option<&HistorySearch>,
+    offset: i64,
+) -> Result<Vec<QueryHistory>, AppError> {
+    let collapse = request.collapse_duplicates.unwrap_or(false);
+    // In the collapsed form the bare columns come from the latest run of each query.
+    let mut sql = String::from(if collapse {
+        "SELECT MAX(qh.history_id) AS history_id, qh.connection_id, qh.query_text, qh.status,
+                qh.execution_time_ms, qh.error_message, qh.executed_at, COUNT(*) AS run_count,
+                EXISTS (SELECT 1 FROM query_history_results r WHERE r.history_id = qh.history_id) AS has_result
+         FROM query_history qh
+         JOIN accessible_connections c ON qh.connection_id = c.connection_id
+         WHERE c.user_id = ?"
+    } else {
+        "SELECT qh.*, 1 AS run_count,
+                EXISTS (SELECT 1 FROM query_history_results r WHERE r.history_id = qh.history_id) AS has_result
+         FROM query_history qh
+         JOIN accessible_connections c ON qh.connection_id = c.connection_id
+         WHERE c.user_id = ?"
+    });
 
     let has_status = request
         .status_filter
@@ -67,6 +231,10 @@ pub async fn get_query_history(
         sql.push_str(" AND qh.status = ?");
     }
 
+    if request.connection_id.is_some() {
+        sql.push_str(" AND qh.connection_id = ?");
+    }
+
     if request.start_date.is_some() {
         sql.push_str(" AND qh.executed_at >= ?");
     }
@@ -74,54 +242,432 @@ pub async fn get_query_history(
     if request.end_date.is_some() {
         sql.push_str(" AND qh.executed_at <= ?");
     }
-    let mut order_clauses = Vec::new();
 
-    let has_search = request
-        .search_query
-        .as_ref()
-        .map(|s| !s.is_empty())
-        .unwrap_or(false);
+    match search {
+        Some(HistorySearch::Words(_)) => sql.push_str(
+            " AND qh.history_id IN (SELECT rowid FROM query_history_fts WHERE query_history_fts MATCH ?)",
+        ),
+        Some(HistorySearch::Substring(_)) => {
+            sql.push_str(" AND qh.query_text LIKE ? ESCAPE '\\'")
+        }
+        None => {}
+    }
 
-    if has_search {
-        order_clauses.push("(qh.query_text LIKE ?) DESC");
+    if collapse {
+        sql.push_str(" GROUP BY qh.connection_id, qh.query_text");
     }
 
     let is_desc = request.sort_desc.unwrap_or(true);
     if is_desc {
-        order_clauses.push("qh.executed_at DESC");
+        sql.push_str(" ORDER BY executed_at DESC, history_id DESC");
     } else {
-        order_clauses.push("qh.executed_at ASC");
-    }
-
-    if !order_clauses.is_empty() {
-        sql.push_str(" ORDER BY ");
-        sql.push_str(&order_clauses.join(", "));
+        sql.push_str(" ORDER BY executed_at ASC, history_id ASC");
     }
 
     sql.push_str(" LIMIT ? OFFSET ?");
 
-    let mut query = sqlx::query_as::<_, QueryHistory>(&sql).bind(request.user_id);
+    let mut query = sqlx::query_as::<_, QueryHistory>(&sql).bind(user_id);
 
     if has_status {
-        query = query.bind(request.status_filter.unwrap());
+        query = query.bind(request.status_filter.as_deref());
+    }
+
+    if let Some(connection_id) = request.connection_id {
+        query = query.bind(connection_id);
     }
 
-    if let Some(start) = request.start_date {
+    if let Some(start) = &request.start_date {
         query = query.bind(start);
     }
 
-    if let Some(end) = request.end_date {
+    if let Some(end) = &request.end_date {
         query = query.bind(end);
     }
 
-    if has_search {
-        if let Some(search) = &request.search_query {
-            let search_pattern = format!("%{search}%");
-            query = query.bind(search_pattern);
+    if let Some(HistorySearch::Words(search) | HistorySearch::Substring(search)) = search {
+        query = query.bind(search);
+    }
+
+    query = query.bind(request.limit).bind(offset);
+
+    query.fetch_all(pool).await.map_err(AppError::from)
+}
+
+async fn load_history_retention(pool: &SqlitePool) -> Result<HistoryRetention, AppError> {
+    sqlx::query_as::<_, HistoryRetention>(
+        "SELECT max_age_days, max_rows FROM history_retention_settings WHERE settings_id = 1",
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Deletes history older than the configured age and beyond the configured
+/// row count. Returns the number of entries removed.
+pub(crate) async fn enforce_history_retention(pool: &SqlitePool) -> Result<u64, AppError> {
+    let retention = load_history_retention(pool).await?;
+    let mut removed = 0;
+
+    if let Some(days) = retention.max_age_days {
+        removed += sqlx::query("DELETE FROM query_history WHERE executed_at < datetime('now', ?)")
+            .bind(format!("-{days} days"))
+            .execute(pool)
+            .await?
+            .rows_affected();
+    }
+
+    if let Some(max_rows) = retention.max_rows {
+        removed += sqlx::query(
+            "DELETE FROM query_history WHERE history_id <= (
+                 SELECT history_id FROM query_history ORDER BY history_id DESC LIMIT 1 OFFSET ?
+             )",
+        )
+        .bind(max_rows)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    }
+
+    Ok(removed)
+}
+
+/// Applies the retention policy now and then every `RETENTION_INTERVAL`.
+pub(crate) fn spawn_history_retention(pool: SqlitePool) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(RETENTION_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = enforce_history_retention(&pool).await {
+                log::error!("Failed to apply query history retention: {e}");
+            }
+        }
+    });
+}
+
+impl ExecutionStats {
+    fn from_runs(runs: &[(String, Option<i64>)]) -> Self {
+        let mut stats = ExecutionStats {
+            total: runs.len() as i64,
+            ..Default::default()
+        };
+        let mut times: Vec<i64> = Vec::new();
+        for (status, execution_time_ms) in runs {
+            match status.as_str() {
+                "success" => stats.success += 1,
+                "timeout" => stats.timeout += 1,
+                _ => stats.error += 1,
+            }
+            times.extend(execution_time_ms);
+        }
+        if stats.total > 0 {
+            stats.success_ratio = Some(stats.success as f64 / stats.total as f64);
         }
+        if !times.is_empty() {
+            times.sort_unstable();
+            stats.avg_execution_ms = Some(times.iter().sum::<i64>() as f64 / times.len() as f64);
+            // Nearest-rank percentile.
+            let rank = (times.len() as f64 * 0.95).ceil() as usize;
+            stats.p95_execution_ms = Some(times[rank.max(1) - 1]);
+        }
+        stats
     }
+}
 
-    query = query.bind(request.limit).bind(request.offset);
+/// Query counts, outcomes and timings for one connection over a date range,
+/// with a per-day series and the most frequent and slowest queries.
+#[tauri::command]
+pub async fn get_connection_statistics(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    request: ConnectionStatisticsRequest,
+) -> Result<ConnectionStatistics, AppError> {
+    let user_id = session.user_id()?;
+    ensure_connection_permission(&pool, user_id, request.connection_id, SharePermission::Use)
+        .await?;
+
+    let to = request.to.unwrap_or_else(|| Utc::now().naive_utc());
+    let from = request
+        .from
+        .unwrap_or_else(|| to - Days::new(DEFAULT_STATISTICS_DAYS));
+    if from > to {
+        return Err(AppError::validation(
+            "The start of the range is after its end",
+        ));
+    }
+    if (to - from).num_days() > MAX_STATISTICS_DAYS {
+        return Err(AppError::validation(format!(
+            "The range can span at most {MAX_STATISTICS_DAYS} days"
+        )));
+    }
+    let top_n = request.top_n.unwrap_or(DEFAULT_TOP_QUERIES).clamp(1, 100);
+
+    let runs: Vec<(NaiveDate, String, Option<i64>)> = sqlx::query_as(
+        "SELECT date(executed_at), status, execution_time_ms FROM query_history
+         WHERE connection_id = ? AND executed_at >= ? AND executed_at <= ?",
+    )
+    .bind(request.connection_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(&*pool)
+    .await?;
+
+    let mut by_day: BTreeMap<NaiveDate, Vec<(String, Option<i64>)>> = from
+        .date()
+        .iter_days()
+        .take_while(|day| *day <= to.date())
+        .map(|day| (day, Vec::new()))
+        .collect();
+    let mut all_runs = Vec::with_capacity(runs.len());
+    for (day, status, execution_time_ms) in runs {
+        by_day
+            .entry(day)
+            .or_default()
+            .push((status.clone(), execution_time_ms));
+        all_runs.push((status, execution_time_ms));
+    }
+
+    let most_frequent = sqlx::query_as::<_, FrequentQuery>(
+        "SELECT query_text, COUNT(*) AS run_count, AVG(execution_time_ms) AS avg_execution_ms,
+                MAX(executed_at) AS last_executed_at
+         FROM query_history
+         WHERE connection_id = ? AND executed_at >= ? AND executed_at <= ?
+         GROUP BY query_text
+         ORDER BY run_count DESC, last_executed_at DESC
+         LIMIT ?",
+    )
+    .bind(request.connection_id)
+    .bind(from)
+    .bind(to)
+    .bind(top_n)
+    .fetch_all(&*pool)
+    .await?;
+
+    let slowest = sqlx::query_as::<_, SlowQuery>(
+        "SELECT history_id, query_text, execution_time_ms, executed_at
+         FROM query_history
+         WHERE connection_id = ? AND executed_at >= ? AND executed_at <= ?
+               AND execution_time_ms IS NOT NULL
+         ORDER BY execution_time_ms DESC
+         LIMIT ?",
+    )
+    .bind(request.connection_id)
+    .bind(from)
+    .bind(to)
+    .bind(top_n)
+    .fetch_all(&*pool)
+    .await?;
+
+    Ok(ConnectionStatistics {
+        connection_id: request.connection_id,
+        from,
+        to,
+        overall: ExecutionStats::from_runs(&all_runs),
+        daily: by_day
+            .into_iter()
+            .map(|(day, runs)| DailyExecutionStats {
+                day,
+                stats: ExecutionStats::from_runs(&runs),
+            })
+            .collect(),
+        most_frequent,
+        slowest,
+    })
+}
+
+async fn load_history_result_settings(
+    pool: &SqlitePool,
+) -> Result<HistoryResultSettings, AppError> {
+    sqlx::query_as::<_, HistoryResultSettings>(
+        "SELECT enabled, max_rows, max_bytes FROM history_result_settings WHERE settings_id = 1",
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Keeps the first rows of a successful run with its history entry, when
+/// snapshots are enabled. Rows past `max_rows` or `max_bytes` are dropped.
+pub(crate) async fn store_history_result(
+    pool: &SqlitePool,
+    history_id: i64,
+    columns: &[ResultColumn],
+    rows: &[Vec<Value>],
+) -> Result<(), AppError> {
+    let settings = load_history_result_settings(pool).await?;
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    let mut json = b"[".to_vec();
+    let mut stored_row_count = 0;
+    for row in rows.iter().take(settings.max_rows as usize) {
+        let encoded = serde_json::to_vec(row).map_err(|e| AppError::internal(e.to_string()))?;
+        if (json.len() + encoded.len() + 2) as i64 > settings.max_bytes {
+            break;
+        }
+        if stored_row_count > 0 {
+            json.push(b',');
+        }
+        json.extend(encoded);
+        stored_row_count += 1;
+    }
+    json.push(b']');
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&json)
+        .map_err(|e| AppError::internal(e.to_string()))?;
+    let rows_gzip = encoder
+        .finish()
+        .map_err(|e| AppError::internal(e.to_string()))?;
+    let columns_json =
+        serde_json::to_string(columns).map_err(|e| AppError::internal(e.to_string()))?;
+
+    sqlx::query(
+        "INSERT INTO query_history_results (history_id, columns_json, rows_gzip, stored_row_count, total_row_count)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(history_id)
+    .bind(columns_json)
+    .bind(rows_gzip)
+    .bind(stored_row_count)
+    .bind(rows.len() as i64)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_history_result(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    history_id: i64,
+) -> Result<HistoryResult, AppError> {
+    let user_id = session.user_id()?;
+    let connection_id: i64 =
+        sqlx::query_scalar("SELECT connection_id FROM query_history WHERE history_id = ?")
+            .bind(history_id)
+            .fetch_optional(&*pool)
+            .await?
+            .ok_or_else(|| AppError::not_found(format!("History entry {history_id} not found")))?;
+    ensure_connection_permission(&pool, user_id, connection_id, SharePermission::Use).await?;
+
+    let (columns_json, rows_gzip, total_row_count): (String, Vec<u8>, i64) = sqlx::query_as(
+        "SELECT columns_json, rows_gzip, total_row_count FROM query_history_results WHERE history_id = ?",
+    )
+    .bind(history_id)
+    .fetch_optional(&*pool)
+    .await?
+    .ok_or_else(|| {
+        AppError::not_found(format!("No result stored for history entry {history_id}"))
+    })?;
+
+    let mut json = Vec::new();
+    GzDecoder::new(rows_gzip.as_slice())
+        .read_to_end(&mut json)
+        .map_err(|e| AppError::internal(format!("Corrupt result snapshot: {e}")))?;
+
+    Ok(HistoryResult {
+        history_id,
+        columns: serde_json::from_str(&columns_json)
+            .map_err(|e| AppError::internal(format!("Corrupt result snapshot: {e}")))?,
+        rows: serde_json::from_slice(&json)
+            .map_err(|e| AppError::internal(format!("Corrupt result snapshot: {e}")))?,
+        total_row_count,
+    })
+}
+
+#[tauri::command]
+pub async fn get_history_result_settings(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+) -> Result<HistoryResultSettings, AppError> {
+    require_role(&pool, &session, Role::Admin).await?;
+    load_history_result_settings(&pool).await
+}
+
+#[tauri::command]
+pub async fn update_history_result_settings(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    settings: HistoryResultSettings,
+) -> Result<(), AppError> {
+    let admin_id = require_role(&pool, &session, Role::Admin).await?;
+    if settings.max_rows < 1 || settings.max_bytes < 1 {
+        return Err(AppError::validation(
+            "Snapshot row and size limits must be at least 1",
+        ));
+    }
+
+    sqlx::query(
+        "UPDATE history_result_settings SET enabled = ?, max_rows = ?, max_bytes = ? WHERE settings_id = 1",
+    )
+    .bind(settings.enabled)
+    .bind(settings.max_rows)
+    .bind(settings.max_bytes)
+    .execute(&*pool)
+    .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        admin_id,
+        "UPDATE_HISTORY_RESULT_SETTINGS",
+        None,
+        json!(settings),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_history_retention(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+) -> Result<HistoryRetention, AppError> {
+    require_role(&pool, &session, Role::Admin).await?;
+    load_history_retention(&pool).await
+}
+
+/// Saves the retention policy and applies it straight away. Returns the number
+/// of entries removed.
+#[tauri::command]
+pub async fn update_history_retention(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    retention: HistoryRetention,
+) -> Result<u64, AppError> {
+    let admin_id = require_role(&pool, &session, Role::Admin).await?;
+    if retention.max_age_days.is_some_and(|days| days < 1)
+        || retention.max_rows.is_some_and(|rows| rows < 1)
+    {
+        return Err(AppError::validation(
+            "Retention limits must be at least 1, or empty for no limit",
+        ));
+    }
+
+    sqlx::query(
+        "UPDATE history_retention_settings SET max_age_days = ?, max_rows = ? WHERE settings_id = 1",
+    )
+    .bind(retention.max_age_days)
+    .bind(retention.max_rows)
+    .execute(&*pool)
+    .await?;
+
+    let removed = enforce_history_retention(&pool).await?;
+
+    let _ = log_action_internal(
+        &pool,
+        admin_id,
+        "UPDATE_HISTORY_RETENTION",
+        None,
+        json!({
+            "max_age_days": retention.max_age_days,
+            "max_rows": retention.max_rows,
+            "removed": removed,
+        }),
+    )
+    .await;
 
-    query.fetch_all(&*pool).await.map_err(|e| e.to_string())
+    Ok(removed)
 }
@@ -0,0 +1,161 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::sessions::{ensure_owner, user_role, Owned, SessionState};
+use crate::error::AppError;
+use crate::models::app_users::Role;
+use crate::models::connections::{Connection, SafetyLevel};
+use crate::sql::classifier::{classify_destructive, escapes_read_only, DestructiveStatement};
+
+use serde_json::json;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+
+const CONFIRMATION_TTL: Duration = Duration::from_secs(5 * 60);
+
+struct PendingConfirmation {
+    connection_id: i64,
+    query_text: String,
+    issued_at: Instant,
+}
+
+/// Single-use tokens handed out by `check_query_safety` for destructive queries
+/// on connections in confirm mode.
+#[derive(Default)]
+pub struct ConfirmationTokens(Mutex<HashMap<String, PendingConfirmation>>);
+
+impl ConfirmationTokens {
+    fn issue(&self, connection_id: i64, query_text: &str) -> Result<String, AppError> {
+        let token = uuid::Uuid::new_v4().to_string();
+        let mut pending = self
+            .0
+            .lock()
+            .map_err(|e| AppError::internal(e.to_string()))?;
+        pending.retain(|_, p| p.issued_at.elapsed() < CONFIRMATION_TTL);
+        pending.insert(
+            token.clone(),
+            PendingConfirmation {
+                connection_id,
+                query_text: query_text.to_string(),
+                issued_at: Instant::now(),
+            },
+        );
+        Ok(token)
+    }
+
+    /// Removes `token` and reports whether it was issued for exactly this query.
+    fn consume(&self, token: &str, connection_id: i64, query_text: &str) -> bool {
+        let Ok(mut pending) = self.0.lock() else {
+            return false;
+        };
+        pending.remove(token).is_some_and(|p| {
+            p.connection_id == connection_id
+                && p.query_text == query_text
+                && p.issued_at.elapsed() < CONFIRMATION_TTL
+        })
+    }
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct QuerySafetyCheck {
+    pub safety_level: SafetyLevel,
+    pub destructive_statements: Vec<DestructiveStatement>,
+    pub requires_confirmation: bool,
+    pub confirmation_token: Option<String>,
+}
+
+/// Rejects `query_text` if the connection's safety level does not allow it to run
+/// as-is. Confirm mode accepts destructive statements only with a token issued
+/// by `check_query_safety` for the same query.
+pub(crate) fn enforce_safety_level(
+    tokens: &ConfirmationTokens,
+    connection: &Connection,
+    query_text: &str,
+    confirmation_token: Option<&str>,
+) -> Result<(), AppError> {
+    match connection.safety_level {
+        SafetyLevel::Unrestricted => Ok(()),
+        SafetyLevel::ReadOnly if escapes_read_only(query_text) => Err(AppError::validation(
+            "Connection is read-only; leaving read-only mode is not allowed",
+        )),
+        SafetyLevel::ReadOnly => Ok(()),
+        SafetyLevel::ConfirmDestructive => {
+            let destructive = classify_destructive(query_text);
+            if destructive.is_empty() {
+                return Ok(());
+            }
+            let confirmed = confirmation_token
+                .is_some_and(|token| tokens.consume(token, connection.connection_id, query_text));
+            if confirmed {
+                Ok(())
+            } else {
+                let statements: Vec<&str> =
+                    destructive.iter().map(|s| s.description.as_str()).collect();
+                Err(AppError::validation(format!(
+                    "Confirmation required for destructive statements: {}",
+                    statements.join("; ")
+                )))
+            }
+        }
+    }
+}
+
+/// `enforce_safety_level`, recording a blocked statement in the audit log.
+pub(crate) async fn enforce_safety_level_logged(
+    pool: &SqlitePool,
+    user_id: i64,
+    tokens: &ConfirmationTokens,
+    connection: &Connection,
+    query_text: &str,
+    confirmation_token: Option<&str>,
+) -> Result<(), AppError> {
+    let result = enforce_safety_level(tokens, connection, query_text, confirmation_token);
+    if let Err(e) = &result {
+        let _ = log_action_internal(
+            pool,
+            user_id,
+            "QUERY_BLOCKED",
+            Some(connection.connection_id),
+            json!({ "query": query_text, "reason": e.message() }),
+        )
+        .await;
+    }
+    result
+}
+
+#[tauri::command]
+pub async fn check_query_safety(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    tokens: State<'_, ConfirmationTokens>,
+    connection_id: i64,
+    query_text: String,
+) -> Result<QuerySafetyCheck, AppError> {
+    let user_id = session.user_id()?;
+    ensure_owner(&pool, user_id, Owned::Connection(connection_id)).await?;
+    let safety_level: SafetyLevel = if user_role(&pool, user_id).await? == Role::Viewer {
+        SafetyLevel::ReadOnly
+    } else {
+        sqlx::query_scalar("SELECT safety_level FROM connections WHERE connection_id = ?")
+            .bind(connection_id)
+            .fetch_one(&*pool)
+            .await?
+    };
+
+    let destructive_statements = classify_destructive(&query_text);
+    let requires_confirmation =
+        safety_level == SafetyLevel::ConfirmDestructive && !destructive_statements.is_empty();
+    let confirmation_token = if requires_confirmation {
+        Some(tokens.issue(connection_id, &query_text)?)
+    } else {
+        None
+    };
+
+    Ok(QuerySafetyCheck {
+        safety_level,
+        destructive_statements,
+        requires_confirmation,
+        confirmation_token,
+    })
+}
@@ -0,0 +1,120 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::sessions::{require_role, SessionState};
+use crate::error::AppError;
+use crate::models::app_users::Role;
+use crate::models::security_settings::SecuritySettings;
+use serde_json::json;
+use sqlx::SqlitePool;
+use tauri::State;
+
+/// One year.
+pub(crate) const MAX_LOCKOUT_MINUTES: i64 = 525_600;
+
+pub(crate) async fn load_security_settings(
+    pool: &SqlitePool,
+) -> Result<SecuritySettings, AppError> {
+    sqlx::query_as::<_, SecuritySettings>(
+        "SELECT min_password_length, require_uppercase, require_lowercase, require_digit,
+                require_symbol, max_failed_logins, lockout_minutes
+         FROM security_settings WHERE settings_id = 1",
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Rejects `password` with every rule of the configured policy it breaks.
+pub(crate) async fn enforce_password_policy(
+    pool: &SqlitePool,
+    password: &str,
+) -> Result<(), AppError> {
+    let policy = load_security_settings(pool).await?;
+    let mut unmet = Vec::new();
+    if (password.chars().count() as i64) < policy.min_password_length {
+        unmet.push(format!(
+            "at least {} characters",
+            policy.min_password_length
+        ));
+    }
+    if policy.require_uppercase && !password.chars().any(char::is_uppercase) {
+        unmet.push("an uppercase letter".to_string());
+    }
+    if policy.require_lowercase && !password.chars().any(char::is_lowercase) {
+        unmet.push("a lowercase letter".to_string());
+    }
+    if policy.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+        unmet.push("a digit".to_string());
+    }
+    if policy.require_symbol && password.chars().all(char::is_alphanumeric) {
+        unmet.push("a symbol".to_string());
+    }
+
+    if unmet.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::validation(format!(
+            "Password must contain {}",
+            unmet.join(", ")
+        )))
+    }
+}
+
+#[tauri::command]
+pub async fn get_security_settings(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+) -> Result<SecuritySettings, AppError> {
+    require_role(&pool, &session, Role::Admin).await?;
+    load_security_settings(&pool).await
+}
+
+#[tauri::command]
+pub async fn update_security_settings(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    settings: SecuritySettings,
+) -> Result<(), AppError> {
+    let admin_id = require_role(&pool, &session, Role::Admin).await?;
+    if settings.min_password_length < 1 {
+        return Err(AppError::validation(
+            "Minimum password length must be at least 1",
+        ));
+    }
+    if settings.max_failed_logins < 1 || settings.lockout_minutes < 1 {
+        return Err(AppError::validation(
+            "Failed login limit and lockout duration must be at least 1",
+        ));
+    }
+    if settings.lockout_minutes > MAX_LOCKOUT_MINUTES {
+        return Err(AppError::validation(format!(
+            "Lockout duration cannot exceed {MAX_LOCKOUT_MINUTES} minutes"
+        )));
+    }
+
+    sqlx::query(
+        "UPDATE security_settings SET min_password_length = ?, require_uppercase = ?,
+                require_lowercase = ?, require_digit = ?, require_symbol = ?,
+                max_failed_logins = ?, lockout_minutes = ?
+         WHERE settings_id = 1",
+    )
+    .bind(settings.min_password_length)
+    .bind(settings.require_uppercase)
+    .bind(settings.require_lowercase)
+    .bind(settings.require_digit)
+    .bind(settings.require_symbol)
+    .bind(settings.max_failed_logins)
+    .bind(settings.lockout_minutes)
+    .execute(&*pool)
+    .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        admin_id,
+        "UPDATE_SECURITY_SETTINGS",
+        None,
+        json!(settings),
+    )
+    .await;
+
+    Ok(())
+}
@@ -0,0 +1,292 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::error::AppError;
+use crate::models::app_users::{AppUser, Role};
+use crate::models::connection_shares::SharePermission;
+
+use serde_json::json;
+use sqlx::SqlitePool;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+const SESSION_MAX_AGE: Duration = Duration::from_secs(12 * 60 * 60);
+
+struct Session {
+    /// Pairs the LOGIN and LOGOUT audit entries. Never used to authenticate.
+    session_id: String,
+    user_id: i64,
+    password_change_required: bool,
+    started_at: Instant,
+    last_seen: Instant,
+}
+
+impl Session {
+    fn is_expired(&self) -> bool {
+        self.last_seen.elapsed() >= SESSION_IDLE_TIMEOUT
+            || self.started_at.elapsed() >= SESSION_MAX_AGE
+    }
+}
+
+/// The signed-in user's session. Commands derive the acting user from here
+/// instead of trusting ids passed by the frontend.
+#[derive(Default)]
+pub struct SessionState(Mutex<Option<Session>>);
+
+impl SessionState {
+    /// Replaces any current session with a new one for `user_id` and returns its id.
+    /// While `password_change_required` is set, only `change_password` may act.
+    pub(crate) fn start(
+        &self,
+        user_id: i64,
+        password_change_required: bool,
+    ) -> Result<String, AppError> {
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let now = Instant::now();
+        *self.lock()? = Some(Session {
+            session_id: session_id.clone(),
+            user_id,
+            password_change_required,
+            started_at: now,
+            last_seen: now,
+        });
+        Ok(session_id)
+    }
+
+    /// Ends the current session, returning its user id and session id.
+    pub(crate) fn end(&self) -> Result<Option<(i64, String)>, AppError> {
+        Ok(self.lock()?.take().map(|s| (s.user_id, s.session_id)))
+    }
+
+    /// The signed-in user's id. Refreshes the idle timer; fails once the session
+    /// has expired, after logout, or while a password change is pending.
+    pub(crate) fn user_id(&self) -> Result<i64, AppError> {
+        let (user_id, password_change_required) = self.touch()?;
+        if password_change_required {
+            return Err(AppError::forbidden(
+                "Password change required before continuing",
+            ));
+        }
+        Ok(user_id)
+    }
+
+    /// Like `user_id`, but also succeeds while a password change is pending.
+    pub(crate) fn signed_in_user_id(&self) -> Result<i64, AppError> {
+        self.touch().map(|(user_id, _)| user_id)
+    }
+
+    /// Lifts the pending password change once the user has picked a new one.
+    pub(crate) fn password_changed(&self) -> Result<(), AppError> {
+        if let Some(session) = self.lock()?.as_mut() {
+            session.password_change_required = false;
+        }
+        Ok(())
+    }
+
+    fn touch(&self) -> Result<(i64, bool), AppError> {
+        let mut current = self.lock()?;
+        match current.as_mut() {
+            Some(session) if session.is_expired() => {
+                *current = None;
+                Err(AppError::auth("Session expired, please sign in again"))
+            }
+            Some(session) => {
+                session.last_seen = Instant::now();
+                Ok((session.user_id, session.password_change_required))
+            }
+            None => Err(AppError::auth("Not signed in")),
+        }
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Option<Session>>, AppError> {
+        self.0.lock().map_err(|e| AppError::internal(e.to_string()))
+    }
+}
+
+/// The role of `user_id`; a user deleted mid-session is treated as signed out.
+pub(crate) async fn user_role(pool: &SqlitePool, user_id: i64) -> Result<Role, AppError> {
+    sqlx::query_scalar::<_, Role>("SELECT role FROM app_users WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::auth("Not signed in"))
+}
+
+/// The signed-in user's id, provided their role includes `required`.
+pub(crate) async fn require_role(
+    pool: &SqlitePool,
+    session: &SessionState,
+    required: Role,
+) -> Result<i64, AppError> {
+    let user_id = session.user_id()?;
+    if user_role(pool, user_id).await?.includes(required) {
+        Ok(user_id)
+    } else {
+        Err(AppError::forbidden(format!(
+            "This action requires the {} role",
+            required.name()
+        )))
+    }
+}
+
+/// A row the signed-in user must own before a command may touch it. Connections,
+/// and the diagrams and pinned queries under them, also count when shared.
+/// Connection-independent pinned queries belong only to their creator.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Owned {
+    Connection(i64),
+    Folder(i64),
+    Tag(i64),
+    Diagram(i64),
+    PinnedQuery(i64),
+    PinnedQueryFolder(i64),
+}
+
+/// Fails with not-found, without revealing whether the row exists, unless
+/// `user_id` owns `resource`.
+pub(crate) async fn ensure_owner(
+    pool: &SqlitePool,
+    user_id: i64,
+    resource: Owned,
+) -> Result<(), AppError> {
+    let (sql, id, name) = match resource {
+        Owned::Connection(id) => (
+            "SELECT 1 FROM accessible_connections WHERE connection_id = ? AND user_id = ?",
+            id,
+            "Connection",
+        ),
+        Owned::Folder(id) => (
+            "SELECT 1 FROM connection_folders WHERE folder_id = ? AND user_id = ?",
+            id,
+            "Folder",
+        ),
+        Owned::Tag(id) => (
+            "SELECT 1 FROM tags WHERE tag_id = ? AND user_id = ?",
+            id,
+            "Tag",
+        ),
+        Owned::Diagram(id) => (
+            "SELECT 1 FROM diagrams d
+             JOIN accessible_connections c ON d.connection_id = c.connection_id
+             WHERE d.diagram_id = ? AND c.user_id = ?",
+            id,
+            "Diagram",
+        ),
+        Owned::PinnedQuery(id) => (
+            "SELECT 1 FROM pinned_queries pq
+             WHERE pq.pinned_query_id = ?1
+               AND (pq.user_id = ?2 OR pq.connection_id IN
+                   (SELECT connection_id FROM accessible_connections WHERE user_id = ?2))",
+            id,
+            "Pinned query",
+        ),
+        Owned::PinnedQueryFolder(id) => (
+            "SELECT 1 FROM pinned_query_folders WHERE folder_id = ? AND user_id = ?",
+            id,
+            "Folder",
+        ),
+    };
+
+    let owned: Option<i64> = sqlx::query_scalar(sql)
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    owned
+        .map(|_| ())
+        .ok_or_else(|| AppError::not_found(format!("{name} {id} not found")))
+}
+
+/// Like `ensure_owner`, for changing or deleting a diagram or pinned query.
+/// Rows under a shared connection also need `Edit` permission on it, unless
+/// `user_id` created them.
+pub(crate) async fn ensure_can_modify(
+    pool: &SqlitePool,
+    user_id: i64,
+    resource: Owned,
+) -> Result<(), AppError> {
+    ensure_owner(pool, user_id, resource).await?;
+    let (sql, id) = match resource {
+        Owned::Diagram(id) => (
+            "SELECT connection_id, NULL FROM diagrams WHERE diagram_id = ?",
+            id,
+        ),
+        Owned::PinnedQuery(id) => (
+            "SELECT connection_id, user_id FROM pinned_queries WHERE pinned_query_id = ?",
+            id,
+        ),
+        _ => return Ok(()),
+    };
+
+    let (connection_id, creator): (Option<i64>, Option<i64>) =
+        sqlx::query_as(sql).bind(id).fetch_one(pool).await?;
+    match connection_id {
+        Some(connection_id) if creator != Some(user_id) => {
+            ensure_connection_permission(pool, user_id, connection_id, SharePermission::Edit)
+                .await
+                .map(|_| ())
+        }
+        // Connection-independent queries already passed `ensure_owner` as their creator.
+        _ => Ok(()),
+    }
+}
+
+/// Fails unless `user_id` has at least `required` access to the connection:
+/// not-found without any access, forbidden with too little.
+pub(crate) async fn ensure_connection_permission(
+    pool: &SqlitePool,
+    user_id: i64,
+    connection_id: i64,
+    required: SharePermission,
+) -> Result<SharePermission, AppError> {
+    let permission: Option<SharePermission> = sqlx::query_scalar(
+        "SELECT permission FROM accessible_connections WHERE connection_id = ? AND user_id = ?",
+    )
+    .bind(connection_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    match permission {
+        Some(permission) if permission.includes(required) => Ok(permission),
+        Some(_) => Err(AppError::forbidden(format!(
+            "This action requires {} permission on connection {connection_id}",
+            required.name()
+        ))),
+        None => Err(AppError::not_found(format!(
+            "Connection {connection_id} not found"
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn logout(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+) -> Result<(), AppError> {
+    if let Some((user_id, session_id)) = session.end()? {
+        let _ = log_action_internal(
+            &pool,
+            user_id,
+            "LOGOUT",
+            None,
+            json!({ "session_id": session_id }),
+        )
+        .await;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_current_user(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+) -> Result<AppUser, AppError> {
+    let user_id = session.signed_in_user_id()?;
+    sqlx::query_as::<_, AppUser>(
+        "SELECT user_id, username, password_hash, role, must_change_password, failed_login_attempts, locked_until, totp_enabled, created_at FROM app_users WHERE user_id = ?",
+    )
+    .bind(user_id)
+    .fetch_optional(&*pool)
+    .await?
+    .ok_or_else(|| AppError::auth("Not signed in"))
+}
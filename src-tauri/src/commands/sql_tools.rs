@@ -0,0 +1,22 @@
+use crate::error::AppError;
+use crate::sql::formatter::{self, FormatOptions};
+use crate::sql::linter::{self, Diagnostic, LintOptions};
+
+#[tauri::command]
+pub async fn format_sql(
+    query_text: String,
+    options: Option<FormatOptions>,
+) -> Result<String, AppError> {
+    Ok(formatter::format_sql(
+        &query_text,
+        &options.unwrap_or_default(),
+    ))
+}
+
+#[tauri::command]
+pub async fn lint_sql(
+    query_text: String,
+    options: Option<LintOptions>,
+) -> Result<Vec<Diagnostic>, AppError> {
+    Ok(linter::lint_sql(&query_text, &options.unwrap_or_default()))
+}
@@ -1,10 +1,13 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::sessions::{ensure_owner, Owned, SessionState};
+use crate::error::AppError;
 use crate::models::tags::Tag;
+use serde_json::json;
 use sqlx::SqlitePool;
 use tauri::State;
 
 #[derive(serde::Deserialize)]
 pub struct CreateTagRequest {
-    pub user_id: i64,
     pub tag_name: String,
     pub color_hex: Option<String>,
 }
@@ -19,60 +22,111 @@ pub struct UpdateTagRequest {
 #[tauri::command]
 pub async fn create_tag(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: CreateTagRequest,
-) -> Result<i64, String> {
-    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+) -> Result<i64, AppError> {
+    let user_id = session.user_id()?;
+    let mut tx = pool.begin().await?;
 
     let id = sqlx::query_scalar::<_, i64>(
         "INSERT INTO tags (user_id, tag_name, color_hex) VALUES (?, ?, ?) RETURNING tag_id",
     )
-    .bind(request.user_id)
-    .bind(request.tag_name)
-    .bind(request.color_hex)
+    .bind(user_id)
+    .bind(&request.tag_name)
+    .bind(&request.color_hex)
     .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?;
+
+    tx.commit().await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "CREATE_TAG",
+        None,
+        json!({ "tag_id": id, "tag_name": request.tag_name, "color_hex": request.color_hex }),
+    )
+    .await;
 
-    tx.commit().await.map_err(|e| e.to_string())?;
     Ok(id)
 }
 
 #[tauri::command]
 pub async fn update_tag(
     pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
     request: UpdateTagRequest,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_owner(&pool, user_id, Owned::Tag(request.tag_id)).await?;
     sqlx::query("UPDATE tags SET tag_name = ?, color_hex = ? WHERE tag_id = ?")
-        .bind(request.tag_name)
-        .bind(request.color_hex)
+        .bind(&request.tag_name)
+        .bind(&request.color_hex)
         .bind(request.tag_id)
         .execute(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "UPDATE_TAG",
+        None,
+        json!({
+            "tag_id": request.tag_id,
+            "tag_name": request.tag_name,
+            "color_hex": request.color_hex,
+        }),
+    )
+    .await;
+
     Ok(())
 }
 
 #[tauri::command]
-pub async fn delete_tag(pool: State<'_, SqlitePool>, tag_id: i64) -> Result<(), String> {
+pub async fn delete_tag(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    tag_id: i64,
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    ensure_owner(&pool, user_id, Owned::Tag(tag_id)).await?;
+
     sqlx::query("DELETE FROM connection_tags WHERE tag_id = ?")
         .bind(tag_id)
         .execute(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
+
+    sqlx::query("DELETE FROM pinned_query_tags WHERE tag_id = ?")
+        .bind(tag_id)
+        .execute(&*pool)
+        .await?;
 
     sqlx::query("DELETE FROM tags WHERE tag_id = ?")
         .bind(tag_id)
         .execute(&*pool)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
+
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "DELETE_TAG",
+        None,
+        json!({ "tag_id": tag_id }),
+    )
+    .await;
+
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_tags(pool: State<'_, SqlitePool>, user_id: i64) -> Result<Vec<Tag>, String> {
+pub async fn get_tags(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+) -> Result<Vec<Tag>, AppError> {
+    let user_id = session.user_id()?;
     sqlx::query_as::<_, Tag>("SELECT * FROM tags WHERE user_id = ?")
         .bind(user_id)
         .fetch_all(&*pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
@@ -0,0 +1,311 @@
+use crate::commands::app_user_logs::log_action_internal;
+use crate::commands::sessions::SessionState;
+use crate::error::AppError;
+use crate::password::{decrypt_data, encrypt_data, hash_password, verify_password};
+
+use serde_json::json;
+use sqlx::SqlitePool;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const TOTP_ISSUER: &str = "pg-manager";
+const TOTP_STEP: u64 = 30;
+/// Codes from this many steps either side of now are accepted, for clock drift.
+const TOTP_SKEW: u64 = 1;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+#[derive(serde::Serialize)]
+pub struct TotpEnrollment {
+    /// Base32 secret for authenticator apps that cannot scan the URI.
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+fn build_totp(encoded_secret: &str, username: &str) -> Result<TOTP, AppError> {
+    let secret = Secret::Encoded(encoded_secret.to_string())
+        .to_bytes()
+        .map_err(|e| AppError::internal(format!("Invalid TOTP secret: {e:?}")))?;
+    Ok(TOTP::new_unchecked(
+        Algorithm::SHA1,
+        6,
+        TOTP_SKEW as u8,
+        TOTP_STEP,
+        secret,
+        Some(TOTP_ISSUER.to_string()),
+        username.to_string(),
+    ))
+}
+
+fn current_step() -> Result<u64, AppError> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / TOTP_STEP)
+        .map_err(|e| AppError::internal(e.to_string()))
+}
+
+/// The time step `code` was generated for, if it is valid near now and newer
+/// than `last_step`.
+fn matching_step(totp: &TOTP, code: &str, last_step: Option<i64>) -> Result<Option<u64>, AppError> {
+    let now = current_step()?;
+    let step = (now.saturating_sub(TOTP_SKEW)..=now + TOTP_SKEW)
+        .find(|step| totp.generate(step * TOTP_STEP) == code)
+        .filter(|step| last_step.map_or(true, |last| *step as i64 > last));
+    Ok(step)
+}
+
+/// Generates fresh recovery codes for `user_id`, replacing any earlier ones.
+/// Only hashes are stored; the plaintext codes are returned to show once.
+async fn issue_recovery_codes(pool: &SqlitePool, user_id: i64) -> Result<Vec<String>, AppError> {
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let hex = uuid::Uuid::new_v4().simple().to_string();
+            format!("{}-{}", &hex[..5], &hex[5..10])
+        })
+        .collect();
+    let hashes = codes
+        .iter()
+        .map(|code| hash_password(code))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM app_user_recovery_codes WHERE user_id = ?")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    for hash in hashes {
+        sqlx::query("INSERT INTO app_user_recovery_codes (user_id, code_hash) VALUES (?, ?)")
+            .bind(user_id)
+            .bind(hash)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    Ok(codes)
+}
+
+/// Marks the unused recovery code matching `code` as used. Returns whether one matched.
+async fn consume_recovery_code(
+    pool: &SqlitePool,
+    user_id: i64,
+    code: &str,
+) -> Result<bool, AppError> {
+    let unused: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT code_id, code_hash FROM app_user_recovery_codes WHERE user_id = ? AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let code = code.trim().to_lowercase();
+    let Some((code_id, _)) = unused
+        .into_iter()
+        .find(|(_, hash)| verify_password(&code, hash))
+    else {
+        return Ok(false);
+    };
+
+    sqlx::query("UPDATE app_user_recovery_codes SET used_at = CURRENT_TIMESTAMP WHERE code_id = ?")
+        .bind(code_id)
+        .execute(pool)
+        .await?;
+    Ok(true)
+}
+
+/// Which second factor a login was completed with.
+pub(crate) enum SecondFactor {
+    Totp,
+    RecoveryCode,
+}
+
+/// Checks `code` as the second login step for a user with 2FA enabled. Accepts
+/// a current TOTP code or an unused recovery code.
+pub(crate) async fn verify_second_factor(
+    pool: &SqlitePool,
+    user_id: i64,
+    username: &str,
+    code: &str,
+) -> Result<Option<SecondFactor>, AppError> {
+    let (secret, last_step): (Option<String>, Option<i64>) = sqlx::query_as(
+        "SELECT totp_secret, totp_last_step FROM app_users WHERE user_id = ? AND totp_enabled = 1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| {
+        AppError::not_found(format!(
+            "Two-factor authentication not enabled for user {user_id}"
+        ))
+    })?;
+    let secret = secret
+        .ok_or_else(|| AppError::internal("Two-factor authentication enabled without a secret"))?;
+
+    let totp = build_totp(
+        &decrypt_data(&secret).map_err(AppError::internal)?,
+        username,
+    )?;
+    if let Some(step) = matching_step(&totp, code.trim(), last_step)? {
+        sqlx::query("UPDATE app_users SET totp_last_step = ? WHERE user_id = ?")
+            .bind(step as i64)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+        return Ok(Some(SecondFactor::Totp));
+    }
+
+    if consume_recovery_code(pool, user_id, code).await? {
+        return Ok(Some(SecondFactor::RecoveryCode));
+    }
+    Ok(None)
+}
+
+/// Clears a user's 2FA secret and recovery codes.
+pub(crate) async fn reset_two_factor(pool: &SqlitePool, user_id: i64) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "UPDATE app_users SET totp_secret = NULL, totp_enabled = 0, totp_last_step = NULL WHERE user_id = ?",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM app_user_recovery_codes WHERE user_id = ?")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Starts enrolment with a new secret. 2FA is not enforced until the first code
+/// is confirmed with `confirm_totp_enrollment`.
+#[tauri::command]
+pub async fn begin_totp_enrollment(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+) -> Result<TotpEnrollment, AppError> {
+    let user_id = session.user_id()?;
+    let (username, enabled): (String, bool) =
+        sqlx::query_as("SELECT username, totp_enabled FROM app_users WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_one(&*pool)
+            .await?;
+    if enabled {
+        return Err(AppError::validation(
+            "Two-factor authentication is already enabled",
+        ));
+    }
+
+    let secret = Secret::generate_secret().to_encoded().to_string();
+    let totp = build_totp(&secret, &username)?;
+    sqlx::query("UPDATE app_users SET totp_secret = ?, totp_last_step = NULL WHERE user_id = ?")
+        .bind(encrypt_data(&secret))
+        .bind(user_id)
+        .execute(&*pool)
+        .await?;
+
+    Ok(TotpEnrollment {
+        otpauth_uri: totp.get_url(),
+        secret,
+    })
+}
+
+/// Enables 2FA once `code` proves the authenticator app holds the pending
+/// secret. Returns the recovery codes, which are never shown again.
+#[tauri::command]
+pub async fn confirm_totp_enrollment(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    code: String,
+) -> Result<Vec<String>, AppError> {
+    let user_id = session.user_id()?;
+    let (username, secret, enabled): (String, Option<String>, bool) = sqlx::query_as(
+        "SELECT username, totp_secret, totp_enabled FROM app_users WHERE user_id = ?",
+    )
+    .bind(user_id)
+    .fetch_one(&*pool)
+    .await?;
+    if enabled {
+        return Err(AppError::validation(
+            "Two-factor authentication is already enabled",
+        ));
+    }
+    let secret = secret.ok_or_else(|| {
+        AppError::validation("No enrolment in progress; start one with begin_totp_enrollment")
+    })?;
+
+    let totp = build_totp(
+        &decrypt_data(&secret).map_err(AppError::internal)?,
+        &username,
+    )?;
+    let Some(step) = matching_step(&totp, code.trim(), None)? else {
+        return Err(AppError::validation("Invalid authentication code"));
+    };
+
+    sqlx::query("UPDATE app_users SET totp_enabled = 1, totp_last_step = ? WHERE user_id = ?")
+        .bind(step as i64)
+        .bind(user_id)
+        .execute(&*pool)
+        .await?;
+    let codes = issue_recovery_codes(&pool, user_id).await?;
+
+    let _ = log_action_internal(&pool, user_id, "TOTP_ENABLED", None, json!({})).await;
+
+    Ok(codes)
+}
+
+/// Replaces the signed-in user's recovery codes with a fresh set.
+#[tauri::command]
+pub async fn regenerate_recovery_codes(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    password: String,
+) -> Result<Vec<String>, AppError> {
+    let user_id = session.user_id()?;
+    let (password_hash, enabled): (String, bool) =
+        sqlx::query_as("SELECT password_hash, totp_enabled FROM app_users WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_one(&*pool)
+            .await?;
+    if !verify_password(&password, &password_hash) {
+        return Err(AppError::validation("Password is incorrect"));
+    }
+    if !enabled {
+        return Err(AppError::validation(
+            "Two-factor authentication is not enabled",
+        ));
+    }
+
+    let codes = issue_recovery_codes(&pool, user_id).await?;
+    let _ = log_action_internal(
+        &pool,
+        user_id,
+        "RECOVERY_CODES_REGENERATED",
+        None,
+        json!({}),
+    )
+    .await;
+    Ok(codes)
+}
+
+/// Turns 2FA off for the signed-in user after re-checking their password.
+#[tauri::command]
+pub async fn disable_totp(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, SessionState>,
+    password: String,
+) -> Result<(), AppError> {
+    let user_id = session.user_id()?;
+    let password_hash: String =
+        sqlx::query_scalar("SELECT password_hash FROM app_users WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_one(&*pool)
+            .await?;
+    if !verify_password(&password, &password_hash) {
+        return Err(AppError::validation("Password is incorrect"));
+    }
+
+    reset_two_factor(&pool, user_id).await?;
+    let _ = log_action_internal(&pool, user_id, "TOTP_DISABLED", None, json!({})).await;
+    Ok(())
+}
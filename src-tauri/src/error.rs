@@ -0,0 +1,184 @@
+use serde::Serialize;
+use sqlx::error::ErrorKind;
+use sqlx::postgres::{PgDatabaseError, PgErrorPosition};
+use std::fmt;
+
+/// Error returned by every command. Serialised with a `kind` tag so the frontend
+/// can tell failures apart; every variant carries a human-readable `message`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AppError {
+    Connection {
+        message: String,
+    },
+    /// The app session is missing or expired.
+    Auth {
+        message: String,
+    },
+    /// Postgres rejected the connection's credentials.
+    DatabaseAuth {
+        message: String,
+    },
+    Forbidden {
+        message: String,
+    },
+    Tls {
+        message: String,
+    },
+    NotFound {
+        message: String,
+    },
+    Validation {
+        message: String,
+    },
+    Postgres(Box<PostgresError>),
+    Internal {
+        message: String,
+    },
+}
+
+/// A server-side error reported by Postgres, with the fields of its
+/// ErrorResponse message.
+#[derive(Debug, Serialize)]
+pub struct PostgresError {
+    pub message: String,
+    pub code: String,
+    pub severity: String,
+    pub detail: Option<String>,
+    pub hint: Option<String>,
+    /// 1-based character offset into the submitted query.
+    pub position: Option<usize>,
+    /// 1-based character offset into `internal_query`, for errors raised by
+    /// queries generated inside functions.
+    pub internal_position: Option<usize>,
+    pub internal_query: Option<String>,
+    pub context: Option<String>,
+    pub schema: Option<String>,
+    pub table: Option<String>,
+    pub column: Option<String>,
+    pub data_type: Option<String>,
+    pub constraint: Option<String>,
+}
+
+impl AppError {
+    pub fn connection(message: impl Into<String>) -> Self {
+        AppError::Connection {
+            message: message.into(),
+        }
+    }
+
+    pub fn auth(message: impl Into<String>) -> Self {
+        AppError::Auth {
+            message: message.into(),
+        }
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        AppError::Forbidden {
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::NotFound {
+            message: message.into(),
+        }
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        AppError::Validation {
+            message: message.into(),
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        AppError::Internal {
+            message: message.into(),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::Connection { message }
+            | AppError::Auth { message }
+            | AppError::DatabaseAuth { message }
+            | AppError::Forbidden { message }
+            | AppError::Tls { message }
+            | AppError::NotFound { message }
+            | AppError::Validation { message }
+            | AppError::Internal { message } => message,
+            AppError::Postgres(e) => &e.message,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<&PgDatabaseError> for PostgresError {
+    fn from(e: &PgDatabaseError) -> Self {
+        let (position, internal_position, internal_query) = match e.position() {
+            Some(PgErrorPosition::Original(position)) => (Some(position), None, None),
+            Some(PgErrorPosition::Internal { position, query }) => {
+                (None, Some(position), Some(query.to_string()))
+            }
+            None => (None, None, None),
+        };
+        PostgresError {
+            message: e.message().to_string(),
+            code: e.code().to_string(),
+            severity: format!("{:?}", e.severity()).to_uppercase(),
+            detail: e.detail().map(str::to_string),
+            hint: e.hint().map(str::to_string),
+            position,
+            internal_position,
+            internal_query,
+            context: e.r#where().map(str::to_string),
+            schema: e.schema().map(str::to_string),
+            table: e.table().map(str::to_string),
+            column: e.column().map(str::to_string),
+            data_type: e.data_type().map(str::to_string),
+            constraint: e.constraint().map(str::to_string),
+        }
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(error: sqlx::Error) -> Self {
+        match error {
+            sqlx::Error::RowNotFound => AppError::not_found("Record not found"),
+            sqlx::Error::Tls(e) => AppError::Tls {
+                message: e.to_string(),
+            },
+            sqlx::Error::Io(_)
+            | sqlx::Error::Configuration(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed => AppError::connection(error.to_string()),
+            sqlx::Error::Database(db_error) => {
+                if let Some(pg_error) = db_error.try_downcast_ref::<PgDatabaseError>() {
+                    // SQLSTATE class 28 is invalid authorization, class 08 connection exceptions.
+                    return match pg_error.code().get(..2) {
+                        Some("28") => AppError::DatabaseAuth {
+                            message: pg_error.message().to_string(),
+                        },
+                        Some("08") => AppError::connection(pg_error.message()),
+                        _ => AppError::Postgres(Box::new(PostgresError::from(pg_error))),
+                    };
+                }
+                match db_error.kind() {
+                    ErrorKind::UniqueViolation
+                    | ErrorKind::ForeignKeyViolation
+                    | ErrorKind::NotNullViolation
+                    | ErrorKind::CheckViolation => AppError::validation(db_error.message()),
+                    _ => AppError::internal(db_error.message()),
+                }
+            }
+            _ => AppError::internal(error.to_string()),
+        }
+    }
+}
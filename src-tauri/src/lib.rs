@@ -1,6 +1,8 @@
 mod commands;
+mod error;
 mod models;
 mod password;
+mod sql;
 
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
 use std::fs;
@@ -8,7 +10,7 @@ use std::str::FromStr;
 use tauri::Manager;
 
 use commands::prelude as cmds;
-use password::hash_password;
+use password::{hash_password, verify_password};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -61,21 +63,50 @@ pub fn run() {
 
                 if user_count == 0 {
                     sqlx::query(
-                        "INSERT INTO app_users (username, password_hash, role) VALUES ('admin', ?, 'admin')",
+                        "INSERT INTO app_users (username, password_hash, role, must_change_password) VALUES ('admin', ?, 'admin', 1)",
                     )
-                    .bind(hash_password("admin"))
+                    .bind(hash_password("admin").expect("failed to hash default password"))
                     .execute(&pool)
                     .await
                     .expect("failed to create default user");
                 }
 
+                // Installs seeded before passwords had to be changed may still use admin/admin.
+                let default_admin: Option<(i64, String)> = sqlx::query_as(
+                    "SELECT user_id, password_hash FROM app_users WHERE username = 'admin' AND must_change_password = 0",
+                )
+                .fetch_optional(&pool)
+                .await
+                .unwrap_or(None);
+                if let Some((user_id, hash)) = default_admin {
+                    if verify_password("admin", &hash) {
+                        let _ = sqlx::query(
+                            "UPDATE app_users SET must_change_password = 1 WHERE user_id = ?",
+                        )
+                        .bind(user_id)
+                        .execute(&pool)
+                        .await;
+                    }
+                }
+
+                cmds::chain_legacy_logs(&pool)
+                    .await
+                    .expect("failed to chain audit log");
+
+                cmds::spawn_history_retention(pool.clone());
+
                 app_handle.manage(pool);
             });
 
+            app.manage(cmds::CompletionCache::default());
+            app.manage(cmds::ConfirmationTokens::default());
+            app.manage(cmds::SessionState::default());
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             cmds::add_connection_tag,
+            cmds::add_pinned_query_tag,
             cmds::add_query_history,
             cmds::create_app_user,
             cmds::create_app_user_log,
@@ -84,14 +115,21 @@ pub fn run() {
             cmds::create_connection_folder,
             cmds::create_diagram,
             cmds::create_pinned_query,
+            cmds::create_pinned_query_folder,
             cmds::create_tag,
             cmds::delete_app_user,
             cmds::delete_connection,
+            cmds::share_connection,
+            cmds::unshare_connection,
+            cmds::get_connection_shares,
             cmds::delete_pinned_query,
+            cmds::delete_pinned_query_folder,
             cmds::delete_connection_folder,
             cmds::delete_tag,
             cmds::execute_query,
             cmds::get_app_user_logs,
+            cmds::export_app_user_logs_csv,
+            cmds::verify_audit_log,
             cmds::get_app_users,
             cmds::get_all_connection_tags,
             cmds::get_bookmarks,
@@ -101,7 +139,15 @@ pub fn run() {
             cmds::get_diagrams,
             cmds::get_schema_columns,
             cmds::get_pinned_queries,
+            cmds::get_pinned_query_folders,
+            cmds::get_pinned_query_versions,
+            cmds::get_all_pinned_query_tags,
             cmds::get_query_history,
+            cmds::get_history_retention,
+            cmds::update_history_retention,
+            cmds::get_history_result,
+            cmds::get_history_result_settings,
+            cmds::update_history_result_settings,
             cmds::get_schemas,
             cmds::get_tables,
             cmds::get_tags,
@@ -110,15 +156,61 @@ pub fn run() {
             cmds::update_app_user,
             cmds::update_connection,
             cmds::update_pinned_query,
+            cmds::update_pinned_query_folder,
+            cmds::restore_pinned_query_version,
             cmds::update_connection_folder,
             cmds::verify_user_credentials,
+            cmds::change_password,
+            cmds::unlock_app_user,
+            cmds::get_security_settings,
+            cmds::update_security_settings,
+            cmds::begin_totp_enrollment,
+            cmds::confirm_totp_enrollment,
+            cmds::regenerate_recovery_codes,
+            cmds::disable_totp,
             cmds::remove_connection_tag,
+            cmds::remove_pinned_query_tag,
             cmds::get_foreign_keys,
             cmds::create_diagram,
             cmds::update_diagram,
             cmds::delete_diagram,
             cmds::get_user_statistics,
+            cmds::get_connection_statistics,
             cmds::update_tag,
+            cmds::get_server_activity,
+            cmds::cancel_backend,
+            cmds::terminate_backend,
+            cmds::get_lock_tree,
+            cmds::get_table_stats,
+            cmds::get_top_statements,
+            cmds::reset_top_statements,
+            cmds::pin_top_statement,
+            cmds::get_roles,
+            cmds::create_role,
+            cmds::alter_role,
+            cmds::drop_role,
+            cmds::get_privileges,
+            cmds::grant_privileges,
+            cmds::revoke_privileges,
+            cmds::get_extensions,
+            cmds::install_extension,
+            cmds::update_extension,
+            cmds::drop_extension,
+            cmds::get_databases,
+            cmds::create_database,
+            cmds::rename_database,
+            cmds::drop_database,
+            cmds::create_schema,
+            cmds::rename_schema,
+            cmds::drop_schema,
+            cmds::compare_schemas,
+            cmds::compare_table_data,
+            cmds::get_completion_catalog,
+            cmds::format_sql,
+            cmds::lint_sql,
+            cmds::check_query_safety,
+            cmds::logout,
+            cmds::get_current_user,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -6,7 +6,13 @@ use sqlx::FromRow;
 pub struct AppUserLog {
     pub log_id: i64,
     pub user_id: i64,
+    #[sqlx(default)]
+    pub username: Option<String>,
     pub action_type: String,
+    pub connection_id: Option<i64>,
+    #[sqlx(default)]
+    pub connection_name: Option<String>,
+    /// JSON object describing the action.
     pub details: Option<String>,
     pub timestamp: Option<NaiveDateTime>,
 }
@@ -2,11 +2,51 @@ use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+/// What a user may do. Viewers only ever get read-only Postgres sessions;
+/// editors may write; admins additionally manage app users.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    #[default]
+    Editor,
+    Viewer,
+}
+
+impl Role {
+    fn rank(self) -> u8 {
+        match self {
+            Role::Viewer => 0,
+            Role::Editor => 1,
+            Role::Admin => 2,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Editor => "editor",
+            Role::Viewer => "viewer",
+        }
+    }
+
+    /// Whether this role grants at least the permissions of `required`.
+    pub fn includes(self, required: Role) -> bool {
+        self.rank() >= required.rank()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct AppUser {
     pub user_id: i64,
     pub username: String,
+    #[serde(skip_serializing)]
     pub password_hash: String,
-    pub role: Option<String>,
+    pub role: Role,
+    pub must_change_password: bool,
+    pub failed_login_attempts: i64,
+    pub locked_until: Option<NaiveDateTime>,
+    pub totp_enabled: bool,
     pub created_at: Option<NaiveDateTime>,
 }
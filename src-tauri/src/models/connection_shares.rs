@@ -0,0 +1,48 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// How much a user may do with a connection. `Use` allows connecting and
+/// running queries, `Edit` also changing its settings, and `Owner` also
+/// deleting it and managing who it is shared with.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum SharePermission {
+    Use,
+    Edit,
+    Owner,
+}
+
+impl SharePermission {
+    fn rank(self) -> u8 {
+        match self {
+            SharePermission::Use => 0,
+            SharePermission::Edit => 1,
+            SharePermission::Owner => 2,
+        }
+    }
+
+    /// Whether this level grants at least the rights of `required`.
+    pub fn includes(self, required: SharePermission) -> bool {
+        self.rank() >= required.rank()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SharePermission::Use => "use",
+            SharePermission::Edit => "edit",
+            SharePermission::Owner => "owner",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ConnectionShare {
+    pub connection_id: i64,
+    pub user_id: i64,
+    pub username: String,
+    pub permission: SharePermission,
+    pub granted_by: Option<i64>,
+    pub granted_at: Option<NaiveDateTime>,
+}
@@ -1,6 +1,17 @@
+use crate::models::connection_shares::SharePermission;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum SafetyLevel {
+    ReadOnly,
+    ConfirmDestructive,
+    #[default]
+    Unrestricted,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Connection {
     pub connection_id: i64,
@@ -11,6 +22,15 @@ pub struct Connection {
     pub port: Option<i64>, // INTEGER in SQLite is i64 usually, nullable
     pub db_name: String,
     pub db_user: String,
+    /// Never sent to the frontend, whoever owns the connection.
+    #[serde(skip_serializing)]
     pub db_password_encrypted: String,
     pub ssl_mode: Option<String>,
+    pub safety_level: SafetyLevel,
+    pub statement_timeout_ms: Option<i64>,
+    pub lock_timeout_ms: Option<i64>,
+    pub idle_in_transaction_timeout_ms: Option<i64>,
+    /// The signed-in user's access level; only filled in by `get_connections`.
+    #[sqlx(default)]
+    pub permission: Option<SharePermission>,
 }
@@ -2,9 +2,13 @@ pub mod app_user_logs;
 pub mod app_users;
 pub mod bookmarks;
 pub mod connection_folders;
+pub mod connection_shares;
 pub mod connection_tags;
 pub mod connections;
 pub mod diagrams;
 pub mod pinned_queries;
+pub mod pinned_query_folders;
+pub mod pinned_query_tags;
 pub mod query_history;
+pub mod security_settings;
 pub mod tags;
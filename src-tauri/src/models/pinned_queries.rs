@@ -5,9 +5,23 @@ use sqlx::FromRow;
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct PinnedQuery {
     pub pinned_query_id: i64,
-    pub connection_id: i64,
+    pub user_id: i64,
+    /// `None` when the query can run against any connection.
+    pub connection_id: Option<i64>,
+    pub folder_id: Option<i64>,
     pub query_name: String,
     pub query_text: String,
     pub description: Option<String>,
     pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct PinnedQueryVersion {
+    pub version_id: i64,
+    pub pinned_query_id: i64,
+    pub version_number: i64,
+    pub query_text: String,
+    pub created_by: Option<i64>,
+    pub created_at: Option<NaiveDateTime>,
 }
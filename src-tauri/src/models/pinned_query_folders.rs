@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct PinnedQueryFolder {
+    pub folder_id: i64,
+    pub user_id: i64,
+    pub folder_name: String,
+}
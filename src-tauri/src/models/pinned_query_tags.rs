@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct PinnedQueryTag {
+    pub tag_id: i64,
+    pub pinned_query_id: i64,
+}
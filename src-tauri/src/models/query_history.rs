@@ -1,5 +1,6 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use sqlx::FromRow;
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -11,4 +12,38 @@ pub struct QueryHistory {
     pub execution_time_ms: Option<i64>,
     pub error_message: Option<String>,
     pub executed_at: Option<NaiveDateTime>,
+    /// Runs represented by this entry; above 1 only when duplicates are collapsed.
+    pub run_count: i64,
+    /// Whether a result snapshot is stored for this run.
+    pub has_result: bool,
+}
+
+/// NULL limits are not enforced.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct HistoryRetention {
+    pub max_age_days: Option<i64>,
+    pub max_rows: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct HistoryResultSettings {
+    pub enabled: bool,
+    pub max_rows: i64,
+    /// Cap on a snapshot's rows as JSON, before compression.
+    pub max_bytes: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResultColumn {
+    pub name: String,
+    pub type_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryResult {
+    pub history_id: i64,
+    pub columns: Vec<ResultColumn>,
+    pub rows: Vec<Vec<Value>>,
+    /// Rows the query returned; more than `rows.len()` when the snapshot was cut.
+    pub total_row_count: i64,
 }
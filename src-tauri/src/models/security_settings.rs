@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct SecuritySettings {
+    pub min_password_length: i64,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    /// Consecutive failed logins before an account is locked.
+    pub max_failed_logins: i64,
+    pub lockout_minutes: i64,
+}
@@ -1,16 +1,27 @@
+use crate::error::AppError;
 use bcrypt::{hash, verify, DEFAULT_COST};
 use magic_crypt::{new_magic_crypt, MagicCryptTrait};
 
 const ENCRYPTION_KEY: &str = "ZppB2dU5srj32H5erPodjbZohz6TKVFm"; // TODO: Move to config
 
-pub fn hash_password(password: &str) -> String {
-    hash(password, DEFAULT_COST).unwrap_or_else(|_| password.to_string())
+/// A hash at `DEFAULT_COST` that no login is meant to match.
+const DUMMY_HASH: &str = "$2b$12$FHYadS0aX9Px3Wb461lfA.jlSvf/ZymGjCwwt42yd0Hl3WfVKvDf6";
+
+pub fn hash_password(password: &str) -> Result<String, AppError> {
+    hash(password, DEFAULT_COST)
+        .map_err(|e| AppError::internal(format!("Failed to hash password: {e}")))
 }
 
 pub fn verify_password(password: &str, hash: &str) -> bool {
     verify(password, hash).unwrap_or(false)
 }
 
+/// Does the work of a password check when there is no hash to check against,
+/// so a missing account takes as long as a wrong password.
+pub fn verify_dummy_password(password: &str) {
+    let _ = verify(password, DUMMY_HASH);
+}
+
 pub fn encrypt_data(data: &str) -> String {
     let mc = new_magic_crypt!(ENCRYPTION_KEY, 256);
     mc.encrypt_str_to_base64(data)
@@ -0,0 +1,300 @@
+use super::lexer::{split_statements, tokenize, Token, TokenKind};
+use super::linter::{lint_sql, LintOptions};
+
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DestructiveKind {
+    Ddl,
+    Drop,
+    Truncate,
+    UnfilteredWrite,
+    /// `MERGE` that updates or deletes the rows it matches.
+    Merge,
+    /// `DO` blocks and procedure calls, whose effects cannot be seen from outside.
+    Procedural,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct DestructiveStatement {
+    pub kind: DestructiveKind,
+    pub description: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Leading keywords of statements that change the schema or privileges,
+/// besides `DROP`.
+const DDL_KEYWORDS: &[&str] = &[
+    "CREATE", "ALTER", "COMMENT", "GRANT", "REVOKE", "REINDEX", "CLUSTER", "IMPORT", "SECURITY",
+];
+
+fn leading_keyword<'t, 'a>(statement: &'t [Token<'a>]) -> Option<&'t Token<'a>> {
+    statement.iter().find(|t| !t.is_trivia())
+}
+
+const EXPLAIN_OPTIONS: &[&str] = &["ANALYZE", "ANALYSE", "VERBOSE"];
+
+/// The keyword that decides what `statement` does, looking through `EXPLAIN`
+/// and its options and through a leading `WITH` clause. `None` for an
+/// `EXPLAIN` without `ANALYZE`, which only plans the statement.
+pub(super) fn effective_keyword<'t, 'a>(statement: &'t [Token<'a>]) -> Option<&'t Token<'a>> {
+    let words: Vec<&Token> = statement.iter().filter(|t| !t.is_trivia()).collect();
+    let mut i = 0;
+
+    if words.first()?.is_keyword("EXPLAIN") {
+        i = 1;
+        let mut analyze = false;
+        if words.get(i).is_some_and(|t| t.kind == TokenKind::LParen) {
+            let close = (i..words.len())
+                .find(|&j| words[j].kind == TokenKind::RParen)
+                .unwrap_or(words.len());
+            analyze = words[i..close]
+                .iter()
+                .any(|t| t.is_keyword("ANALYZE") || t.is_keyword("ANALYSE"));
+            i = close + 1;
+        } else {
+            while let Some(word) = words
+                .get(i)
+                .filter(|t| EXPLAIN_OPTIONS.iter().any(|k| t.is_keyword(k)))
+            {
+                analyze |= !word.is_keyword("VERBOSE");
+                i += 1;
+            }
+        }
+        if !analyze {
+            return None;
+        }
+    }
+
+    while words.get(i).is_some_and(|t| t.kind == TokenKind::LParen) {
+        i += 1;
+    }
+
+    if words.get(i)?.is_keyword("WITH") {
+        // The main statement is the first top-level word after a CTE body.
+        let mut depth = 0usize;
+        for j in i + 1..words.len() {
+            match words[j].kind {
+                TokenKind::LParen => depth += 1,
+                TokenKind::RParen => depth = depth.saturating_sub(1),
+                TokenKind::Word
+                    if depth == 0
+                        && words[j - 1].kind == TokenKind::RParen
+                        && !words[j].is_keyword("AS") =>
+                {
+                    return Some(words[j]);
+                }
+                _ => {}
+            }
+        }
+        return None;
+    }
+
+    words.get(i).copied()
+}
+
+/// Statements in `sql` that change the schema or can discard data in bulk:
+/// DDL, `DROP`, `TRUNCATE`, `UPDATE`/`DELETE` without a `WHERE` clause,
+/// `MERGE` with `THEN UPDATE`/`THEN DELETE`, and `DO`/`CALL`, which may do any
+/// of these.
+pub fn classify_destructive(sql: &str) -> Vec<DestructiveStatement> {
+    let tokens = tokenize(sql);
+    let mut found = Vec::new();
+
+    for statement in split_statements(&tokens) {
+        let Some(first) = leading_keyword(statement) else {
+            continue;
+        };
+        let Some(keyword) = effective_keyword(statement) else {
+            continue;
+        };
+        let kind = if keyword.is_keyword("DROP") {
+            Some(DestructiveKind::Drop)
+        } else if keyword.is_keyword("TRUNCATE") {
+            Some(DestructiveKind::Truncate)
+        } else if DDL_KEYWORDS.iter().any(|k| keyword.is_keyword(k)) {
+            Some(DestructiveKind::Ddl)
+        } else if keyword.is_keyword("MERGE") && merge_writes(statement) {
+            Some(DestructiveKind::Merge)
+        } else if keyword.is_keyword("DO") || keyword.is_keyword("CALL") {
+            Some(DestructiveKind::Procedural)
+        } else {
+            None
+        };
+
+        if let Some(kind) = kind {
+            let words: Vec<&str> = statement
+                .iter()
+                .skip_while(|t| !std::ptr::eq(*t, keyword))
+                .filter(|t| !t.is_trivia())
+                .take(3)
+                .map(|t| t.text)
+                .collect();
+            found.push(DestructiveStatement {
+                kind,
+                description: words.join(" "),
+                line: first.line,
+                column: first.column,
+            });
+        }
+    }
+
+    found.extend(
+        lint_sql(sql, &LintOptions::default())
+            .into_iter()
+            .filter(|d| d.rule == "missing_where")
+            .map(|d| DestructiveStatement {
+                kind: DestructiveKind::UnfilteredWrite,
+                description: d.message,
+                line: d.line,
+                column: d.column,
+            }),
+    );
+    found.sort_by_key(|s| (s.line, s.column));
+    found
+}
+
+fn merge_writes(statement: &[Token]) -> bool {
+    let words: Vec<&Token> = statement.iter().filter(|t| !t.is_trivia()).collect();
+    words.windows(2).any(|pair| {
+        pair[0].is_keyword("THEN") && (pair[1].is_keyword("UPDATE") || pair[1].is_keyword("DELETE"))
+    })
+}
+
+/// Whether any statement in `sql` changes the schema, so cached catalog data
+/// such as completions is stale afterwards.
+pub fn changes_schema(sql: &str) -> bool {
+    let tokens = tokenize(sql);
+    split_statements(&tokens)
+        .into_iter()
+        .filter_map(effective_keyword)
+        .any(|keyword| {
+            keyword.is_keyword("DROP") || DDL_KEYWORDS.iter().any(|k| keyword.is_keyword(k))
+        })
+}
+
+const READ_ONLY_SETTINGS: &[&str] = &["default_transaction_read_only", "transaction_read_only"];
+
+/// Whether `sql` tries to leave read-only mode, e.g. `BEGIN READ WRITE` or
+/// `SET default_transaction_read_only = off`.
+pub fn escapes_read_only(sql: &str) -> bool {
+    let tokens = tokenize(sql);
+    let words: Vec<&Token> = tokens.iter().filter(|t| !t.is_trivia()).collect();
+    let read_write = words
+        .windows(2)
+        .any(|pair| pair[0].is_keyword("READ") && pair[1].is_keyword("WRITE"));
+    let touches_setting = words.iter().any(|t| {
+        let name = t.text.trim_matches(|c| c == '"' || c == '\'');
+        READ_ONLY_SETTINGS
+            .iter()
+            .any(|setting| name.eq_ignore_ascii_case(setting))
+    });
+    read_write || touches_setting
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(sql: &str) -> Vec<DestructiveKind> {
+        classify_destructive(sql)
+            .into_iter()
+            .map(|s| s.kind)
+            .collect()
+    }
+
+    #[test]
+    fn classifies_statements() {
+        assert_eq!(kinds("DROP TABLE t"), [DestructiveKind::Drop]);
+        assert_eq!(kinds("truncate t"), [DestructiveKind::Truncate]);
+        assert_eq!(kinds("ALTER TABLE t ADD c int"), [DestructiveKind::Ddl]);
+        assert_eq!(kinds("GRANT SELECT ON t TO r"), [DestructiveKind::Ddl]);
+        assert_eq!(kinds("DELETE FROM t"), [DestructiveKind::UnfilteredWrite]);
+        assert_eq!(kinds("DO $$BEGIN END$$"), [DestructiveKind::Procedural]);
+        assert_eq!(kinds("CALL p()"), [DestructiveKind::Procedural]);
+        assert!(kinds("SELECT 1; DELETE FROM t WHERE id = 1").is_empty());
+    }
+
+    #[test]
+    fn description_and_position() {
+        let found = classify_destructive("SELECT 1;\n-- c\n  DROP TABLE IF EXISTS t");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].description, "DROP TABLE IF");
+        assert_eq!((found[0].line, found[0].column), (3, 3));
+    }
+
+    #[test]
+    fn explain() {
+        assert!(kinds("EXPLAIN DROP TABLE t").is_empty());
+        assert!(kinds("EXPLAIN VERBOSE TRUNCATE t").is_empty());
+        assert_eq!(
+            kinds("EXPLAIN ANALYZE TRUNCATE t"),
+            [DestructiveKind::Truncate]
+        );
+        assert_eq!(
+            kinds("EXPLAIN (ANALYZE, BUFFERS) DROP TABLE t"),
+            [DestructiveKind::Drop]
+        );
+        assert!(kinds("EXPLAIN (COSTS OFF) DROP TABLE t").is_empty());
+        assert!(kinds("EXPLAIN DELETE FROM t").is_empty());
+        assert!(kinds("EXPLAIN (COSTS OFF) UPDATE t SET a = 1").is_empty());
+        assert_eq!(
+            kinds("EXPLAIN ANALYZE DELETE FROM t"),
+            [DestructiveKind::UnfilteredWrite]
+        );
+        let found = classify_destructive("EXPLAIN ANALYSE VERBOSE DROP TABLE t");
+        assert_eq!(found[0].description, "DROP TABLE t");
+    }
+
+    #[test]
+    fn merge() {
+        assert_eq!(
+            kinds("MERGE INTO t USING s ON t.id = s.id WHEN MATCHED THEN DELETE"),
+            [DestructiveKind::Merge]
+        );
+        assert_eq!(
+            kinds(
+                "MERGE INTO t USING s ON t.id = s.id \
+                 WHEN MATCHED THEN UPDATE SET a = s.a \
+                 WHEN NOT MATCHED THEN INSERT VALUES (s.id, s.a)"
+            ),
+            [DestructiveKind::Merge]
+        );
+        assert!(kinds(
+            "MERGE INTO t USING s ON t.id = s.id WHEN NOT MATCHED THEN INSERT VALUES (s.id)"
+        )
+        .is_empty());
+        assert!(kinds("EXPLAIN MERGE INTO t USING s ON true WHEN MATCHED THEN DELETE").is_empty());
+    }
+
+    #[test]
+    fn with() {
+        assert_eq!(
+            kinds("WITH x AS (SELECT 1), y AS (SELECT 2) DELETE FROM t"),
+            [DestructiveKind::UnfilteredWrite]
+        );
+        assert!(kinds("WITH x AS (SELECT 1) SELECT * FROM x").is_empty());
+        assert!(kinds("(SELECT 1)").is_empty());
+    }
+
+    #[test]
+    fn changes_schema_ignores_literals_and_comments() {
+        assert!(changes_schema("SELECT 1; CREATE TABLE t (a int)"));
+        assert!(changes_schema("/* x */ (DROP TABLE t)"));
+        assert!(changes_schema("EXPLAIN ANALYZE CREATE TABLE t AS SELECT 1"));
+        assert!(!changes_schema("SELECT 'a; DROP TABLE t'"));
+        assert!(!changes_schema("SELECT 1 -- ; ALTER TABLE t"));
+        assert!(!changes_schema("EXPLAIN CREATE TABLE t AS SELECT 1"));
+        assert!(!changes_schema("INSERT INTO t VALUES (1)"));
+    }
+
+    #[test]
+    fn escapes_read_only_mode() {
+        assert!(escapes_read_only("BEGIN READ WRITE"));
+        assert!(escapes_read_only("SET default_transaction_read_only = off"));
+        assert!(escapes_read_only(
+            "SELECT set_config('transaction_read_only', 'off', false)"
+        ));
+        assert!(!escapes_read_only("SELECT read, write FROM t"));
+    }
+}
@@ -0,0 +1,584 @@
+use super::lexer::{split_statements, tokenize, Token, TokenKind};
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum KeywordCase {
+    #[default]
+    Upper,
+    Lower,
+    Preserve,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CommaStyle {
+    #[default]
+    Trailing,
+    Leading,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct FormatOptions {
+    pub keyword_case: KeywordCase,
+    pub indent_width: usize,
+    pub comma_style: CommaStyle,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            keyword_case: KeywordCase::Upper,
+            indent_width: 4,
+            comma_style: CommaStyle::Trailing,
+        }
+    }
+}
+
+pub const KEYWORDS: &[&str] = &[
+    "ADD",
+    "ALL",
+    "ALTER",
+    "ANALYZE",
+    "AND",
+    "ANY",
+    "ARRAY",
+    "AS",
+    "ASC",
+    "BEGIN",
+    "BETWEEN",
+    "BIGINT",
+    "BOOLEAN",
+    "BY",
+    "CASCADE",
+    "CASE",
+    "CAST",
+    "CHECK",
+    "COLLATE",
+    "COLUMN",
+    "COMMIT",
+    "CONCURRENTLY",
+    "CONFLICT",
+    "CONSTRAINT",
+    "COPY",
+    "CREATE",
+    "CROSS",
+    "CURRENT_DATE",
+    "CURRENT_TIMESTAMP",
+    "CURRENT_USER",
+    "DATABASE",
+    "DEFAULT",
+    "DELETE",
+    "DESC",
+    "DISTINCT",
+    "DO",
+    "DROP",
+    "ELSE",
+    "END",
+    "EXCEPT",
+    "EXISTS",
+    "EXPLAIN",
+    "EXTENSION",
+    "FALSE",
+    "FETCH",
+    "FILTER",
+    "FIRST",
+    "FOR",
+    "FOREIGN",
+    "FROM",
+    "FULL",
+    "FUNCTION",
+    "GRANT",
+    "GROUP",
+    "HAVING",
+    "IF",
+    "ILIKE",
+    "IN",
+    "INDEX",
+    "INNER",
+    "INSERT",
+    "INTEGER",
+    "INTERSECT",
+    "INTERVAL",
+    "INTO",
+    "IS",
+    "JOIN",
+    "KEY",
+    "LAST",
+    "LATERAL",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "MATERIALIZED",
+    "NATURAL",
+    "NOT",
+    "NOTHING",
+    "NULL",
+    "NULLS",
+    "OFFSET",
+    "ON",
+    "ONLY",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "OVER",
+    "PARTITION",
+    "PRIMARY",
+    "PROCEDURE",
+    "RECURSIVE",
+    "REFERENCES",
+    "RENAME",
+    "REPLACE",
+    "RETURNING",
+    "REVOKE",
+    "RIGHT",
+    "ROLLBACK",
+    "ROWS",
+    "SCHEMA",
+    "SELECT",
+    "SEQUENCE",
+    "SET",
+    "SIMILAR",
+    "TABLE",
+    "TEMPORARY",
+    "TEXT",
+    "THEN",
+    "TO",
+    "TRIGGER",
+    "TRUE",
+    "TRUNCATE",
+    "UNION",
+    "UNIQUE",
+    "UPDATE",
+    "USING",
+    "VACUUM",
+    "VALUES",
+    "VARCHAR",
+    "VIEW",
+    "WHEN",
+    "WHERE",
+    "WINDOW",
+    "WITH",
+    "WITHIN",
+];
+
+/// Keywords that start a new line when they open a clause.
+const CLAUSE_KEYWORDS: &[&str] = &[
+    "SELECT",
+    "FROM",
+    "WHERE",
+    "GROUP",
+    "ORDER",
+    "HAVING",
+    "LIMIT",
+    "OFFSET",
+    "FETCH",
+    "UNION",
+    "INTERSECT",
+    "EXCEPT",
+    "INSERT",
+    "VALUES",
+    "UPDATE",
+    "SET",
+    "DELETE",
+    "RETURNING",
+    "WINDOW",
+    "ON CONFLICT",
+];
+
+const JOIN_KEYWORDS: &[&str] = &["JOIN", "INNER", "LEFT", "RIGHT", "FULL", "CROSS", "NATURAL"];
+
+/// Clauses whose comma-separated items are placed on separate lines.
+const LIST_CLAUSES: &[&str] = &[
+    "SELECT",
+    "FROM",
+    "GROUP",
+    "ORDER",
+    "SET",
+    "RETURNING",
+    "VALUES",
+];
+
+const CONDITION_CLAUSES: &[&str] = &["WHERE", "HAVING", "JOIN"];
+
+/// Keywords that end an operand, so a `-` or `+` after them is binary.
+const OPERAND_KEYWORDS: &[&str] = &[
+    "BIGINT",
+    "BOOLEAN",
+    "CURRENT_DATE",
+    "CURRENT_TIMESTAMP",
+    "CURRENT_USER",
+    "END",
+    "FALSE",
+    "INTEGER",
+    "NULL",
+    "TEXT",
+    "TRUE",
+    "VARCHAR",
+];
+
+const DML_KEYWORDS: &[&str] = &["SELECT", "WITH", "INSERT", "UPDATE", "DELETE", "VALUES"];
+
+pub fn is_keyword(word: &str) -> bool {
+    KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(word))
+}
+
+struct Frame {
+    /// Whether the parenthesis holds a subquery that gets its own clause layout.
+    subquery: bool,
+    clause: &'static str,
+    between: bool,
+}
+
+struct Formatter<'o> {
+    options: &'o FormatOptions,
+    out: String,
+    frames: Vec<Frame>,
+    dml: bool,
+    /// Set after `.`, `::`, `(` and `[` so the next token attaches without a space.
+    glue: bool,
+}
+
+impl<'o> Formatter<'o> {
+    fn depth(&self) -> usize {
+        self.frames.iter().filter(|f| f.subquery).count()
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.frames
+            .last_mut()
+            .expect("formatter always has a root frame")
+    }
+
+    fn clause(&self) -> &'static str {
+        self.frames.last().map(|f| f.clause).unwrap_or_default()
+    }
+
+    fn in_clause_context(&self) -> bool {
+        self.dml && self.frames.last().is_some_and(|f| f.subquery)
+    }
+
+    fn trim_trailing_spaces(&mut self) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+    }
+
+    fn newline(&mut self, extra_indent: usize) {
+        self.trim_trailing_spaces();
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+        let indent = (self.depth().saturating_sub(1) + extra_indent) * self.options.indent_width;
+        self.out.push_str(&" ".repeat(indent));
+        self.glue = false;
+    }
+
+    fn emit(&mut self, text: &str) {
+        let at_line_start =
+            self.out.is_empty() || self.out.ends_with('\n') || self.out.ends_with(' ');
+        if !at_line_start && !self.glue {
+            self.out.push(' ');
+        }
+        self.out.push_str(text);
+        self.glue = false;
+    }
+
+    fn emit_glued(&mut self, text: &str) {
+        self.trim_trailing_spaces();
+        self.out.push_str(text);
+        self.glue = false;
+    }
+
+    fn keyword_text(&self, text: &str) -> String {
+        match self.options.keyword_case {
+            KeywordCase::Upper => text.to_uppercase(),
+            KeywordCase::Lower => text.to_lowercase(),
+            KeywordCase::Preserve => text.to_string(),
+        }
+    }
+}
+
+/// Whether a sign after `prev` applies to the operand that follows it.
+fn is_prefix(prev: Option<&Token>) -> bool {
+    prev.map_or(true, |p| match p.kind {
+        TokenKind::Word => is_keyword(p.text) && !OPERAND_KEYWORDS.iter().any(|k| p.is_keyword(k)),
+        TokenKind::Operator
+        | TokenKind::Comma
+        | TokenKind::LParen
+        | TokenKind::LBracket
+        | TokenKind::DoubleColon
+        | TokenKind::LineComment
+        | TokenKind::BlockComment => true,
+        _ => false,
+    })
+}
+
+fn clause_for(tokens: &[&Token], i: usize) -> Option<&'static str> {
+    let token = tokens[i];
+    let next = tokens.get(i + 1);
+    if token.is_keyword("ON") && next.is_some_and(|t| t.is_keyword("CONFLICT")) {
+        return Some("ON CONFLICT");
+    }
+    if JOIN_KEYWORDS.iter().any(|k| token.is_keyword(k))
+        && !next.is_some_and(|t| t.kind == TokenKind::LParen)
+    {
+        return Some("JOIN");
+    }
+    CLAUSE_KEYWORDS
+        .iter()
+        .find(|k| !k.contains(' ') && token.is_keyword(k))
+        .copied()
+}
+
+fn format_statement(tokens: &[Token], options: &FormatOptions) -> String {
+    let tokens: Vec<&Token> = tokens
+        .iter()
+        .filter(|t| t.kind != TokenKind::Whitespace)
+        .collect();
+
+    let first_word = tokens
+        .iter()
+        .find(|t| !t.is_trivia())
+        .map(|t| t.text)
+        .unwrap_or_default();
+    let mut f = Formatter {
+        options,
+        out: String::new(),
+        frames: vec![Frame {
+            subquery: true,
+            clause: "",
+            between: false,
+        }],
+        dml: DML_KEYWORDS
+            .iter()
+            .any(|k| first_word.eq_ignore_ascii_case(k)),
+        glue: false,
+    };
+
+    for (i, token) in tokens.iter().enumerate() {
+        let prev = if i > 0 { Some(tokens[i - 1]) } else { None };
+        let next = tokens.get(i + 1).copied();
+
+        match token.kind {
+            TokenKind::LineComment => {
+                f.emit(token.text.trim_end());
+                f.newline(usize::from(!f.clause().is_empty()));
+            }
+            TokenKind::Word => {
+                let text = if is_keyword(token.text) {
+                    f.keyword_text(token.text)
+                } else {
+                    token.text.to_string()
+                };
+
+                let clause = if f.in_clause_context() {
+                    clause_for(&tokens, i)
+                } else {
+                    None
+                };
+                // Keep compound keywords such as DELETE FROM, LEFT JOIN,
+                // IS DISTINCT FROM and DO UPDATE on one line.
+                let continues_previous = prev.is_some_and(|p| {
+                    p.is_keyword("DELETE")
+                        || p.is_keyword("DISTINCT")
+                        || p.is_keyword("DO")
+                        || JOIN_KEYWORDS.iter().any(|k| p.is_keyword(k))
+                });
+
+                if let Some(clause) = clause.filter(|_| !continues_previous) {
+                    if !f.out.is_empty() {
+                        f.newline(usize::from(clause == "JOIN"));
+                    }
+                    f.emit(&text);
+                    let frame = f.frame();
+                    frame.clause = clause;
+                    frame.between = false;
+                } else if token.is_keyword("BETWEEN") {
+                    f.frame().between = true;
+                    f.emit(&text);
+                } else if (token.is_keyword("AND") || token.is_keyword("OR"))
+                    && f.in_clause_context()
+                    && CONDITION_CLAUSES.contains(&f.clause())
+                    && !std::mem::take(&mut f.frame().between)
+                {
+                    f.newline(1 + usize::from(f.clause() == "JOIN"));
+                    f.emit(&text);
+                } else {
+                    f.emit(&text);
+                }
+            }
+            TokenKind::Comma => {
+                let breaks = f.in_clause_context() && LIST_CLAUSES.contains(&f.clause());
+                match (breaks, options.comma_style) {
+                    (true, CommaStyle::Trailing) => {
+                        f.emit_glued(",");
+                        f.newline(1);
+                    }
+                    (true, CommaStyle::Leading) => {
+                        f.newline(1);
+                        f.emit(",");
+                    }
+                    (false, _) => f.emit_glued(","),
+                }
+            }
+            TokenKind::LParen => {
+                let subquery = f.dml
+                    && next.is_some_and(|n| {
+                        n.is_keyword("SELECT") || n.is_keyword("WITH") || n.is_keyword("VALUES")
+                    });
+                let function_call = prev.is_some_and(|p| {
+                    (p.kind == TokenKind::Word && !is_keyword(p.text))
+                        || p.kind == TokenKind::QuotedIdent
+                        || p.kind == TokenKind::RBracket
+                });
+                if function_call {
+                    f.emit_glued("(");
+                } else {
+                    f.emit("(");
+                }
+                f.frames.push(Frame {
+                    subquery,
+                    clause: "",
+                    between: false,
+                });
+                if subquery {
+                    f.newline(0);
+                }
+                f.glue = true;
+            }
+            TokenKind::RParen => {
+                let closes_subquery =
+                    f.frames.len() > 1 && f.frames.pop().is_some_and(|fr| fr.subquery);
+                if closes_subquery {
+                    f.newline(0);
+                    f.emit(")");
+                } else {
+                    f.emit_glued(")");
+                }
+            }
+            TokenKind::Dot | TokenKind::DoubleColon | TokenKind::LBracket => {
+                f.emit_glued(token.text);
+                f.glue = true;
+            }
+            TokenKind::RBracket | TokenKind::Semicolon => f.emit_glued(token.text),
+            TokenKind::Operator if matches!(token.text, "-" | "+") && is_prefix(prev) => {
+                f.emit(token.text);
+                f.glue = true;
+            }
+            _ => f.emit(token.text),
+        }
+    }
+
+    f.out.trim_end().to_string()
+}
+
+pub fn format_sql(sql: &str, options: &FormatOptions) -> String {
+    let tokens = tokenize(sql);
+    let statements: Vec<String> = split_statements(&tokens)
+        .into_iter()
+        .map(|statement| {
+            let mut formatted = format_statement(statement, options);
+            // A trailing line comment would swallow the terminating semicolon.
+            let ends_with_comment = statement
+                .iter()
+                .rev()
+                .find(|t| t.kind != TokenKind::Whitespace)
+                .is_some_and(|t| t.kind == TokenKind::LineComment);
+            if ends_with_comment {
+                formatted.push('\n');
+            }
+            formatted
+        })
+        .collect();
+
+    if statements.is_empty() {
+        return String::new();
+    }
+    let mut formatted = statements.join(";\n\n");
+    formatted.push_str(";\n");
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(sql: &str) -> String {
+        format_sql(sql, &FormatOptions::default())
+    }
+
+    #[test]
+    fn keeps_prefixed_literals_intact() {
+        for literal in ["X'1F'", "B'0101'", "U&'d\\0061t'", "N'abc'", "E'a\\'b'"] {
+            assert_eq!(
+                format(&format!("select {literal}")),
+                format!("SELECT {literal};\n")
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_unary_minus_attached() {
+        assert_eq!(format("select -1"), "SELECT -1;\n");
+        assert_eq!(
+            format("select a from t where b=-1 and c in (-2, +3)"),
+            "SELECT a\nFROM t\nWHERE b = -1\n    AND c IN (-2, +3);\n"
+        );
+        assert_eq!(format("select a - 1, a-b"), "SELECT a - 1,\n    a - b;\n");
+        assert_eq!(
+            format("select case when a then 1 else -1 end - 2"),
+            "SELECT CASE WHEN a THEN 1 ELSE -1 END - 2;\n"
+        );
+    }
+
+    #[test]
+    fn clause_layout() {
+        assert_eq!(
+            format("select a, b from t left join u on t.id = u.id where a = 1 or b = 2 order by a"),
+            "SELECT a,\n    b\nFROM t\n    LEFT JOIN u ON t.id = u.id\nWHERE a = 1\n    OR b = 2\nORDER BY a;\n"
+        );
+    }
+
+    #[test]
+    fn between_and_stays_on_one_line() {
+        assert_eq!(
+            format("select a from t where a between 1 and 2 and b = 3"),
+            "SELECT a\nFROM t\nWHERE a BETWEEN 1 AND 2\n    AND b = 3;\n"
+        );
+    }
+
+    #[test]
+    fn subqueries_are_indented() {
+        assert_eq!(
+            format("select a from (select b from t) s"),
+            "SELECT a\nFROM (\n    SELECT b\n    FROM t\n) s;\n"
+        );
+    }
+
+    #[test]
+    fn options() {
+        let options = FormatOptions {
+            keyword_case: KeywordCase::Lower,
+            indent_width: 2,
+            comma_style: CommaStyle::Leading,
+        };
+        assert_eq!(
+            format_sql("SELECT a, b FROM t", &options),
+            "select a\n  , b\nfrom t;\n"
+        );
+    }
+
+    #[test]
+    fn comments_and_dollar_quotes_are_preserved() {
+        assert_eq!(
+            format("select 1 -- one\n; select $$a  b$$"),
+            "SELECT 1 -- one\n;\n\nSELECT $$a  b$$;\n"
+        );
+        assert_eq!(format("select /* x */ 1"), "SELECT /* x */ 1;\n");
+    }
+
+    #[test]
+    fn empty_input() {
+        assert_eq!(format("  ;  ; "), "");
+    }
+}
@@ -0,0 +1,409 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Whitespace,
+    LineComment,
+    BlockComment,
+    String,
+    QuotedIdent,
+    Number,
+    Word,
+    Parameter,
+    Operator,
+    Comma,
+    Semicolon,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Dot,
+    DoubleColon,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// 1-based line of the first character.
+    pub line: usize,
+    /// 1-based column (in characters) of the first character.
+    pub column: usize,
+}
+
+impl<'a> Token<'a> {
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(keyword)
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self.kind,
+            TokenKind::Whitespace | TokenKind::LineComment | TokenKind::BlockComment
+        )
+    }
+
+    /// Line and column just past the last character of the token.
+    pub fn end_position(&self) -> (usize, usize) {
+        let mut line = self.line;
+        let mut column = self.column;
+        for c in self.text.chars() {
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+        (line, column)
+    }
+}
+
+const OPERATOR_CHARS: &str = "+-*/<>=~!@#%^&|`?:";
+
+/// Splits `sql` into tokens, including whitespace and comments, so that
+/// concatenating every token text reproduces the input.
+pub fn tokenize(sql: &str) -> Vec<Token<'_>> {
+    let chars: Vec<(usize, char)> = sql.char_indices().collect();
+    let byte_at = |i: usize| chars.get(i).map(|(b, _)| *b).unwrap_or(sql.len());
+    let char_at = |i: usize| chars.get(i).map(|(_, c)| *c);
+
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let mut line = 1;
+    let mut column = 1;
+
+    while i < chars.len() {
+        let c = chars[i].1;
+        let next = char_at(i + 1);
+        let start = i;
+
+        let kind = if c.is_whitespace() {
+            while char_at(i).is_some_and(char::is_whitespace) {
+                i += 1;
+            }
+            TokenKind::Whitespace
+        } else if c == '-' && next == Some('-') {
+            while char_at(i).is_some_and(|c| c != '\n') {
+                i += 1;
+            }
+            TokenKind::LineComment
+        } else if c == '/' && next == Some('*') {
+            // Block comments nest in PostgreSQL.
+            let mut depth = 0;
+            while i < chars.len() {
+                if char_at(i) == Some('/') && char_at(i + 1) == Some('*') {
+                    depth += 1;
+                    i += 2;
+                } else if char_at(i) == Some('*') && char_at(i + 1) == Some('/') {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            TokenKind::BlockComment
+        } else if let Some(prefix) = quote_prefix_len(&chars, i, '\'') {
+            let backslash_escapes = prefix == 1 && c.eq_ignore_ascii_case(&'e');
+            i += prefix + 1;
+            while let Some(c) = char_at(i) {
+                if backslash_escapes && c == '\\' {
+                    i += 2;
+                } else if c == '\'' {
+                    if char_at(i + 1) == Some('\'') {
+                        i += 2;
+                    } else {
+                        i += 1;
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            TokenKind::String
+        } else if let Some(prefix) = quote_prefix_len(&chars, i, '"') {
+            i += prefix + 1;
+            while let Some(c) = char_at(i) {
+                i += 1;
+                if c == '"' {
+                    if char_at(i) == Some('"') {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+            }
+            TokenKind::QuotedIdent
+        } else if c == '$' && next.is_some_and(|c| c.is_ascii_digit()) {
+            i += 1;
+            while char_at(i).is_some_and(|c| c.is_ascii_digit()) {
+                i += 1;
+            }
+            TokenKind::Parameter
+        } else if c == '$' && dollar_tag_len(&chars, i).is_some() {
+            let tag_len = dollar_tag_len(&chars, i).unwrap_or(2);
+            let tag = &sql[byte_at(i)..byte_at(i + tag_len)];
+            i += tag_len;
+            match sql[byte_at(i)..].find(tag) {
+                Some(offset) => {
+                    let end_byte = byte_at(i) + offset + tag.len();
+                    while byte_at(i) < end_byte {
+                        i += 1;
+                    }
+                }
+                None => i = chars.len(),
+            }
+            TokenKind::String
+        } else if c.is_ascii_digit() || (c == '.' && next.is_some_and(|c| c.is_ascii_digit())) {
+            while char_at(i).is_some_and(|c| c.is_ascii_digit() || c == '.' || c == '_') {
+                i += 1;
+            }
+            if char_at(i).is_some_and(|c| c == 'e' || c == 'E') {
+                let sign = char_at(i + 1).is_some_and(|c| c == '+' || c == '-');
+                let digit_at = if sign { i + 2 } else { i + 1 };
+                if char_at(digit_at).is_some_and(|c| c.is_ascii_digit()) {
+                    i = digit_at;
+                    while char_at(i).is_some_and(|c| c.is_ascii_digit()) {
+                        i += 1;
+                    }
+                }
+            }
+            TokenKind::Number
+        } else if c.is_alphabetic() || c == '_' {
+            while char_at(i).is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$') {
+                i += 1;
+            }
+            TokenKind::Word
+        } else if c == ':' && next == Some(':') {
+            i += 2;
+            TokenKind::DoubleColon
+        } else if OPERATOR_CHARS.contains(c) {
+            i += 1;
+            while let Some(c) = char_at(i) {
+                let starts_comment = (c == '-' && char_at(i + 1) == Some('-'))
+                    || (c == '/' && char_at(i + 1) == Some('*'));
+                if !OPERATOR_CHARS.contains(c) || starts_comment || c == ':' {
+                    break;
+                }
+                i += 1;
+            }
+            // As in PostgreSQL, `+` and `-` only end a multi-character operator
+            // that also contains one of ~ ! @ # % ^ & | ` ?, so `=-1` is `=` `-1`.
+            let operator = &chars[start..i];
+            if !operator.iter().any(|(_, c)| "~!@#%^&|`?".contains(*c)) {
+                while i > start + 1 && matches!(char_at(i - 1), Some('+' | '-')) {
+                    i -= 1;
+                }
+            }
+            TokenKind::Operator
+        } else {
+            i += 1;
+            match c {
+                ',' => TokenKind::Comma,
+                ';' => TokenKind::Semicolon,
+                '(' => TokenKind::LParen,
+                ')' => TokenKind::RParen,
+                '[' => TokenKind::LBracket,
+                ']' => TokenKind::RBracket,
+                '.' => TokenKind::Dot,
+                _ => TokenKind::Operator,
+            }
+        };
+
+        let token = Token {
+            kind,
+            text: &sql[byte_at(start)..byte_at(i)],
+            line,
+            column,
+        };
+        (line, column) = token.end_position();
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+/// Number of characters before the opening `quote` of a literal starting at
+/// `i`: 0 for a bare quote, 1 for `E'`, `X'`, `B'` and `N'`, 2 for `U&'` and
+/// `U&"`. `None` when no literal starts there.
+fn quote_prefix_len(chars: &[(usize, char)], i: usize, quote: char) -> Option<usize> {
+    let char_at = |i: usize| chars.get(i).map(|(_, c)| c.to_ascii_uppercase());
+    match (char_at(i)?, char_at(i + 1), char_at(i + 2)) {
+        (c, _, _) if c == quote => Some(0),
+        ('E' | 'X' | 'B' | 'N', Some('\''), _) if quote == '\'' => Some(1),
+        ('U', Some('&'), Some(c)) if c == quote => Some(2),
+        _ => None,
+    }
+}
+
+/// Length in characters of a `$tag$` dollar-quote delimiter starting at `i`.
+fn dollar_tag_len(chars: &[(usize, char)], i: usize) -> Option<usize> {
+    let mut j = i + 1;
+    while let Some((_, c)) = chars.get(j) {
+        if *c == '$' {
+            return Some(j - i + 1);
+        }
+        let valid = if j == i + 1 {
+            c.is_alphabetic() || *c == '_'
+        } else {
+            c.is_alphanumeric() || *c == '_'
+        };
+        if !valid {
+            return None;
+        }
+        j += 1;
+    }
+    None
+}
+
+/// Splits tokens into statements on top-level semicolons, dropping empty ones.
+pub fn split_statements<'t, 'a>(tokens: &'t [Token<'a>]) -> Vec<&'t [Token<'a>]> {
+    let mut statements = Vec::new();
+    let mut start = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind == TokenKind::Semicolon {
+            statements.push(&tokens[start..i]);
+            start = i + 1;
+        }
+    }
+    statements.push(&tokens[start..]);
+    statements
+        .into_iter()
+        .filter(|statement| statement.iter().any(|t| !t.is_trivia()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(sql: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(sql)
+            .into_iter()
+            .filter(|t| t.kind != TokenKind::Whitespace)
+            .map(|t| (t.kind, t.text))
+            .collect()
+    }
+
+    #[test]
+    fn prefixed_literals_are_single_tokens() {
+        for literal in [
+            "'plain'",
+            "'it''s'",
+            "E'a\\'b'",
+            "e'\\n'",
+            "X'1F'",
+            "x'1f'",
+            "B'0101'",
+            "N'text'",
+            "U&'d\\0061t'",
+            "u&'d\\0061t'",
+        ] {
+            assert_eq!(
+                kinds(literal),
+                vec![(TokenKind::String, literal)],
+                "{literal}"
+            );
+        }
+        assert_eq!(
+            kinds("U&\"d\\0061t\""),
+            vec![(TokenKind::QuotedIdent, "U&\"d\\0061t\"")]
+        );
+    }
+
+    #[test]
+    fn prefix_letters_without_quote_are_words() {
+        assert_eq!(
+            kinds("x + b"),
+            vec![
+                (TokenKind::Word, "x"),
+                (TokenKind::Operator, "+"),
+                (TokenKind::Word, "b"),
+            ]
+        );
+        assert_eq!(
+            kinds("U & 'a'"),
+            vec![
+                (TokenKind::Word, "U"),
+                (TokenKind::Operator, "&"),
+                (TokenKind::String, "'a'"),
+            ]
+        );
+    }
+
+    #[test]
+    fn dollar_quoting() {
+        assert_eq!(kinds("$$a;b$$"), vec![(TokenKind::String, "$$a;b$$")]);
+        assert_eq!(
+            kinds("$fn$ $$ ; $fn$ $1"),
+            vec![
+                (TokenKind::String, "$fn$ $$ ; $fn$"),
+                (TokenKind::Parameter, "$1"),
+            ]
+        );
+        assert_eq!(kinds("$x$open"), vec![(TokenKind::String, "$x$open")]);
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            kinds("a -- c;\nb"),
+            vec![
+                (TokenKind::Word, "a"),
+                (TokenKind::LineComment, "-- c;"),
+                (TokenKind::Word, "b"),
+            ]
+        );
+        assert_eq!(
+            kinds("/* a /* b */ c */d"),
+            vec![
+                (TokenKind::BlockComment, "/* a /* b */ c */"),
+                (TokenKind::Word, "d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn trailing_sign_is_not_part_of_an_operator() {
+        assert_eq!(
+            kinds("a=-1"),
+            vec![
+                (TokenKind::Word, "a"),
+                (TokenKind::Operator, "="),
+                (TokenKind::Operator, "-"),
+                (TokenKind::Number, "1"),
+            ]
+        );
+        assert_eq!(
+            kinds("a@-1"),
+            vec![
+                (TokenKind::Word, "a"),
+                (TokenKind::Operator, "@-"),
+                (TokenKind::Number, "1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_reproduce_the_input() {
+        let sql = "SELECT E'\\'' , $q$x$q$ /* c */ -- d\nFROM \"t\"\"x\" WHERE a::int>=-1;";
+        let joined: String = tokenize(sql).iter().map(|t| t.text).collect();
+        assert_eq!(joined, sql);
+    }
+
+    #[test]
+    fn positions() {
+        let tokens = tokenize("a\n  bc");
+        let bc = tokens.last().unwrap();
+        assert_eq!((bc.line, bc.column), (2, 3));
+        assert_eq!(bc.end_position(), (2, 5));
+    }
+
+    #[test]
+    fn splits_on_top_level_semicolons_only() {
+        let tokens = tokenize("SELECT ';'; -- x;\n; SELECT $$;$$");
+        let statements = split_statements(&tokens);
+        assert_eq!(statements.len(), 2);
+    }
+}
@@ -0,0 +1,298 @@
+use super::classifier::effective_keyword;
+use super::lexer::{split_statements, tokenize, Token, TokenKind};
+
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct Diagnostic {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct LintOptions {
+    /// Enables rules that only matter for saved queries, such as `SELECT *`.
+    pub pinned_query: bool,
+}
+
+#[derive(Default)]
+struct Frame<'t, 'a> {
+    clause: &'static str,
+    write: Option<&'t Token<'a>>,
+    has_where: bool,
+}
+
+const CLAUSES: &[&str] = &[
+    "SELECT",
+    "FROM",
+    "WHERE",
+    "GROUP",
+    "ORDER",
+    "HAVING",
+    "LIMIT",
+    "OFFSET",
+    "USING",
+    "SET",
+    "RETURNING",
+    "VALUES",
+    "JOIN",
+    "ON",
+];
+
+fn diagnostic(
+    rule: &'static str,
+    severity: Severity,
+    message: &str,
+    from: &Token,
+    to: &Token,
+) -> Diagnostic {
+    let (end_line, end_column) = to.end_position();
+    Diagnostic {
+        rule,
+        severity,
+        message: message.to_string(),
+        line: from.line,
+        column: from.column,
+        end_line,
+        end_column,
+    }
+}
+
+fn check_missing_where(frame: &Frame, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(verb) = frame.write.filter(|_| !frame.has_where) {
+        diagnostics.push(diagnostic(
+            "missing_where",
+            Severity::Error,
+            &format!(
+                "{} without a WHERE clause affects every row in the table",
+                verb.text.to_uppercase()
+            ),
+            verb,
+            verb,
+        ));
+    }
+}
+
+fn lint_statement(tokens: &[Token], options: &LintOptions, diagnostics: &mut Vec<Diagnostic>) {
+    // EXPLAIN without ANALYZE only plans the statement, so it writes nothing.
+    let executes = effective_keyword(tokens).is_some();
+    let tokens: Vec<&Token> = tokens.iter().filter(|t| !t.is_trivia()).collect();
+    let mut frames: Vec<Frame> = vec![Frame::default()];
+
+    for (i, token) in tokens.iter().enumerate() {
+        let prev = if i > 0 { Some(tokens[i - 1]) } else { None };
+        let next = tokens.get(i + 1).copied();
+
+        match token.kind {
+            TokenKind::LParen => {
+                frames.push(Frame::default());
+                continue;
+            }
+            TokenKind::RParen if frames.len() > 1 => {
+                if let Some(closed) = frames.pop() {
+                    check_missing_where(&closed, diagnostics);
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        let frame = frames.last_mut().expect("linter always has a root frame");
+        match token.kind {
+            TokenKind::Word => {
+                if (token.is_keyword("UPDATE") || token.is_keyword("DELETE"))
+                    && executes
+                    && starts_statement(prev)
+                {
+                    frame.write = Some(token);
+                } else if token.is_keyword("WHERE") {
+                    frame.has_where = true;
+                }
+
+                if token.is_keyword("NOT")
+                    && next.is_some_and(|n| n.is_keyword("IN"))
+                    && tokens
+                        .get(i + 2)
+                        .is_some_and(|t| t.kind == TokenKind::LParen)
+                    && tokens
+                        .get(i + 3)
+                        .is_some_and(|t| t.is_keyword("SELECT") || t.is_keyword("WITH"))
+                {
+                    diagnostics.push(diagnostic(
+                        "not_in_subquery",
+                        Severity::Warning,
+                        "NOT IN with a subquery returns no rows if the subquery yields a NULL; use NOT EXISTS instead",
+                        token,
+                        tokens[i + 1],
+                    ));
+                }
+
+                let is_join = ["JOIN", "INNER", "LEFT", "RIGHT", "FULL", "CROSS", "NATURAL"]
+                    .iter()
+                    .any(|k| token.is_keyword(k))
+                    && !next.is_some_and(|n| n.kind == TokenKind::LParen);
+                let after_distinct = prev.is_some_and(|p| p.is_keyword("DISTINCT"));
+                if is_join {
+                    frame.clause = "JOIN";
+                } else if let Some(clause) = CLAUSES.iter().find(|c| token.is_keyword(c)) {
+                    if !after_distinct {
+                        frame.clause = clause;
+                    }
+                }
+            }
+            TokenKind::Comma
+                if frame.clause == "FROM" && !next.is_some_and(|n| n.is_keyword("LATERAL")) =>
+            {
+                diagnostics.push(diagnostic(
+                    "implicit_cross_join",
+                    Severity::Warning,
+                    "Comma-separated FROM items form an implicit cross join; use an explicit JOIN",
+                    token,
+                    token,
+                ));
+            }
+            TokenKind::Operator if token.text == "*" && options.pinned_query => {
+                let in_select_list = frame.clause == "SELECT"
+                    && prev.is_some_and(|p| {
+                        p.is_keyword("SELECT")
+                            || p.is_keyword("DISTINCT")
+                            || p.is_keyword("ALL")
+                            || matches!(p.kind, TokenKind::Comma | TokenKind::Dot)
+                    });
+                if in_select_list {
+                    diagnostics.push(diagnostic(
+                        "select_star",
+                        Severity::Warning,
+                        "Avoid SELECT * in saved queries; list the columns explicitly",
+                        token,
+                        token,
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for frame in frames.iter().rev() {
+        check_missing_where(frame, diagnostics);
+    }
+}
+
+/// Whether a keyword after `prev` begins a (sub)statement, e.g. the DELETE in
+/// `WITH x AS (...) DELETE FROM t` or `EXPLAIN ANALYZE DELETE FROM t` as
+/// opposed to the UPDATE in `FOR UPDATE`.
+fn starts_statement(prev: Option<&Token>) -> bool {
+    prev.map_or(true, |p| {
+        matches!(p.kind, TokenKind::LParen | TokenKind::RParen)
+            || ["EXPLAIN", "ANALYZE", "ANALYSE", "VERBOSE"]
+                .iter()
+                .any(|k| p.is_keyword(k))
+    })
+}
+
+pub fn lint_sql(sql: &str, options: &LintOptions) -> Vec<Diagnostic> {
+    let tokens = tokenize(sql);
+    let mut diagnostics = Vec::new();
+    for statement in split_statements(&tokens) {
+        lint_statement(statement, options, &mut diagnostics);
+    }
+    diagnostics.sort_by_key(|d| (d.line, d.column));
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(sql: &str, options: &LintOptions) -> Vec<&'static str> {
+        lint_sql(sql, options).into_iter().map(|d| d.rule).collect()
+    }
+
+    fn default_rules(sql: &str) -> Vec<&'static str> {
+        rules(sql, &LintOptions::default())
+    }
+
+    #[test]
+    fn missing_where() {
+        assert_eq!(default_rules("DELETE FROM t"), ["missing_where"]);
+        assert_eq!(default_rules("update t set a = 1"), ["missing_where"]);
+        assert!(default_rules("DELETE FROM t WHERE id = 1").is_empty());
+        assert!(default_rules("UPDATE t SET a = 1 WHERE id = 1").is_empty());
+        assert!(default_rules("SELECT * FROM t FOR UPDATE").is_empty());
+        assert_eq!(
+            default_rules("WITH x AS (SELECT 1) DELETE FROM t"),
+            ["missing_where"]
+        );
+        assert_eq!(
+            default_rules("WITH x AS (DELETE FROM t RETURNING *) SELECT * FROM x WHERE a = 1"),
+            ["missing_where"]
+        );
+        assert_eq!(
+            default_rules("EXPLAIN ANALYZE UPDATE t SET a = 1"),
+            ["missing_where"]
+        );
+        assert!(default_rules("EXPLAIN DELETE FROM t").is_empty());
+        assert!(default_rules("EXPLAIN VERBOSE UPDATE t SET a = 1").is_empty());
+        assert!(
+            default_rules("MERGE INTO t USING s ON t.id = s.id WHEN MATCHED THEN DELETE")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn missing_where_position() {
+        let diagnostics = lint_sql("SELECT 1;\n  DELETE FROM t", &LintOptions::default());
+        assert_eq!(diagnostics.len(), 1);
+        let d = &diagnostics[0];
+        assert_eq!(d.severity, Severity::Error);
+        assert_eq!((d.line, d.column, d.end_line, d.end_column), (2, 3, 2, 9));
+    }
+
+    #[test]
+    fn not_in_subquery() {
+        assert_eq!(
+            default_rules("SELECT a FROM t WHERE a NOT IN (SELECT b FROM u)"),
+            ["not_in_subquery"]
+        );
+        assert!(default_rules("SELECT a FROM t WHERE a NOT IN (1, 2)").is_empty());
+        assert!(default_rules("SELECT a FROM t WHERE a IN (SELECT b FROM u)").is_empty());
+    }
+
+    #[test]
+    fn implicit_cross_join() {
+        assert_eq!(
+            default_rules("SELECT a FROM t, u WHERE t.id = u.id"),
+            ["implicit_cross_join"]
+        );
+        assert!(default_rules("SELECT a FROM t, LATERAL f(t.x)").is_empty());
+        assert!(default_rules("SELECT a, b FROM t JOIN u ON t.id = u.id").is_empty());
+        assert!(default_rules("SELECT a FROM t ORDER BY a, b").is_empty());
+    }
+
+    #[test]
+    fn select_star_only_for_pinned_queries() {
+        let pinned = LintOptions { pinned_query: true };
+        assert_eq!(rules("SELECT * FROM t", &pinned), ["select_star"]);
+        assert_eq!(rules("SELECT t.* FROM t", &pinned), ["select_star"]);
+        assert!(rules("SELECT count(*) FROM t", &pinned).is_empty());
+        assert!(rules("SELECT a * 2 FROM t", &pinned).is_empty());
+        assert!(default_rules("SELECT * FROM t").is_empty());
+    }
+
+    #[test]
+    fn ignores_keywords_in_literals_and_comments() {
+        assert!(default_rules("SELECT 'DELETE FROM t' -- UPDATE t\n").is_empty());
+        assert!(default_rules("SELECT $$DELETE FROM t$$").is_empty());
+    }
+}
@@ -0,0 +1,4 @@
+pub mod classifier;
+pub mod formatter;
+pub mod lexer;
+pub mod linter;
@@ -1,10 +1,17 @@
 import { invoke } from "@tauri-apps/api/core";
+import { errorMessage } from "$lib/utils/errors";
+
+export type UserRole = "admin" | "editor" | "viewer";
 
 // Тип, соответствующий модели Rust
 export interface AppUser {
   user_id: number;
   username: string;
-  role: string | null;
+  role: UserRole;
+  must_change_password: boolean;
+  failed_login_attempts: number;
+  locked_until: string | null;
+  totp_enabled: boolean;
   created_at?: string;
 }
 
@@ -12,19 +19,25 @@ let isAuthenticated = $state(false);
 let user = $state<AppUser | null>(null);
 let error = $state<string | null>(null);
 
+export type LoginOutcome =
+  | { status: "success"; user: AppUser }
+  | { status: "invalid_credentials" }
+  | { status: "totp_required" }
+  | { status: "invalid_totp_code" };
+
+let isTotpRequired = $state(false);
+
+// Rejects when the account is locked or the backend fails.
 let verifyUserCredentials = async (
   username: string,
-  password: string
-): Promise<AppUser | null> => {
-  let result = await invoke<AppUser>("verify_user_credentials", {
+  password: string,
+  totpCode: string | null
+): Promise<LoginOutcome> => {
+  return await invoke<LoginOutcome>("verify_user_credentials", {
     username: username,
     password: password,
-  }).catch((err) => {
-    console.error(err);
-    return null;
+    totpCode: totpCode,
   });
-
-  return result || null;
 };
 
 export const authState = {
@@ -34,26 +47,78 @@ export const authState = {
   get user() {
     return user;
   },
-  login: async (username: string, password: string) => {
+  get isTotpRequired() {
+    return isTotpRequired;
+  },
+  get mustChangePassword() {
+    return user?.must_change_password ?? false;
+  },
+  login: async (
+    username: string,
+    password: string,
+    totpCode: string | null = null
+  ) => {
     if (!username || !password) {
       error = "Invalid username or password";
       return;
     }
 
-    const result = await verifyUserCredentials(username, password);
-
-    if (!result) {
-      error = "Invalid username or password";
+    let result: LoginOutcome;
+    try {
+      result = await verifyUserCredentials(username, password, totpCode);
+    } catch (err) {
+      console.error(err);
+      isTotpRequired = false;
+      error = errorMessage(err, "Login failed");
       return;
     }
 
+    switch (result.status) {
+      case "invalid_credentials":
+        isTotpRequired = false;
+        error = "Invalid username or password, or the account is locked";
+        return;
+      case "totp_required":
+        isTotpRequired = true;
+        error = null;
+        return;
+      case "invalid_totp_code":
+        error = "Invalid authentication code";
+        return;
+    }
+
     isAuthenticated = true;
+    isTotpRequired = false;
 
-    user = result;
+    user = result.user;
+    error = null;
+  },
+  changePassword: async (currentPassword: string, newPassword: string) => {
+    try {
+      await invoke("change_password", {
+        request: {
+          current_password: currentPassword,
+          new_password: newPassword,
+        },
+      });
+    } catch (err) {
+      error = errorMessage(err, "Failed to change password");
+      return;
+    }
+    if (user) user = { ...user, must_change_password: false };
+    error = null;
+  },
+  cancelTotp: () => {
+    isTotpRequired = false;
     error = null;
   },
-  logout: () => {
+  setTotpEnabled: (enabled: boolean) => {
+    if (user) user = { ...user, totp_enabled: enabled };
+  },
+  logout: async () => {
+    await invoke("logout").catch((err) => console.error(err));
     isAuthenticated = false;
+    isTotpRequired = false;
     user = null;
     error = null;
   },
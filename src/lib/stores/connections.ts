@@ -2,6 +2,8 @@ import { writable, get } from "svelte/store";
 import { invoke } from "@tauri-apps/api/core";
 import { authState } from "./auth.svelte";
 
+export type SharePermission = "use" | "edit" | "owner";
+
 export interface Connection {
   connection_id: number;
   folder_id?: number;
@@ -13,6 +15,8 @@ export interface Connection {
   db_user: string;
   db_password_encrypted: string;
   ssl_mode?: string;
+  // The signed-in user's access; "owner" for connections they created.
+  permission?: SharePermission | null;
 }
 
 export const connections = writable<Connection[]>([]);
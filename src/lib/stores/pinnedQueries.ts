@@ -4,14 +4,34 @@ import { authState } from "./auth.svelte";
 
 export interface PinnedQuery {
   pinned_query_id: number;
-  connection_id: number;
+  user_id: number;
+  // null when the query can run against any connection
+  connection_id: number | null;
+  folder_id: number | null;
   query_name: string;
   query_text: string;
   description?: string;
   created_at: string;
+  updated_at: string | null;
+}
+
+export interface PinnedQueryVersion {
+  version_id: number;
+  pinned_query_id: number;
+  version_number: number;
+  query_text: string;
+  created_by: number | null;
+  created_at: string;
+}
+
+export interface PinnedQueryFolder {
+  folder_id: number;
+  user_id: number;
+  folder_name: string;
 }
 
 export const pinnedQueries = writable<PinnedQuery[]>([]);
+export const pinnedQueryFolders = writable<PinnedQueryFolder[]>([]);
 export const pinnedSearch = writable<string>("");
 export const pinnedSortAsc = writable<boolean>(false);
 
@@ -35,7 +55,8 @@ export const loadPinnedQueries = async (userId?: number) => {
 };
 
 export const createPinnedQuery = async (payload: {
-  connection_id: number;
+  connection_id: number | null;
+  folder_id: number | null;
   query_name: string;
   query_text: string;
   description?: string;
@@ -51,7 +72,13 @@ export const createPinnedQuery = async (payload: {
 
 export const updatePinnedQuery = async (
   id: number,
-  payload: { query_name: string; description?: string }
+  payload: {
+    connection_id: number | null;
+    folder_id: number | null;
+    query_name: string;
+    query_text: string;
+    description?: string;
+  }
 ) => {
   try {
     await invoke("update_pinned_query", {
@@ -73,3 +100,75 @@ export const deletePinnedQuery = async (id: number) => {
     throw error;
   }
 };
+
+export const loadPinnedQueryVersions = async (id: number) => {
+  return await invoke<PinnedQueryVersion[]>("get_pinned_query_versions", {
+    pinnedQueryId: id,
+  });
+};
+
+export const restorePinnedQueryVersion = async (
+  id: number,
+  versionId: number
+) => {
+  try {
+    const restored = await invoke<PinnedQuery>(
+      "restore_pinned_query_version",
+      { pinnedQueryId: id, versionId }
+    );
+    await loadPinnedQueries();
+    return restored;
+  } catch (error) {
+    console.error("Failed to restore pinned query version:", error);
+    throw error;
+  }
+};
+
+export const loadPinnedQueryFolders = async () => {
+  try {
+    const result = await invoke<PinnedQueryFolder[]>(
+      "get_pinned_query_folders"
+    );
+    pinnedQueryFolders.set(result);
+  } catch (error) {
+    console.error("Failed to load pinned query folders:", error);
+  }
+};
+
+export const createPinnedQueryFolder = async (folderName: string) => {
+  try {
+    await invoke("create_pinned_query_folder", {
+      request: { folder_name: folderName },
+    });
+    await loadPinnedQueryFolders();
+  } catch (error) {
+    console.error("Failed to create pinned query folder:", error);
+    throw error;
+  }
+};
+
+export const updatePinnedQueryFolder = async (
+  folderId: number,
+  folderName: string
+) => {
+  try {
+    await invoke("update_pinned_query_folder", {
+      request: { folder_id: folderId, folder_name: folderName },
+    });
+    await loadPinnedQueryFolders();
+  } catch (error) {
+    console.error("Failed to update pinned query folder:", error);
+    throw error;
+  }
+};
+
+export const deletePinnedQueryFolder = async (folderId: number) => {
+  try {
+    await invoke("delete_pinned_query_folder", { folderId });
+    await loadPinnedQueryFolders();
+    await loadPinnedQueries();
+  } catch (error) {
+    console.error("Failed to delete pinned query folder:", error);
+    throw error;
+  }
+};
@@ -6,10 +6,12 @@ export interface QueryHistoryItem {
   history_id: number;
   connection_id: number;
   query_text: string;
-  status: "success" | "error";
+  status: "success" | "error" | "timeout";
   execution_time_ms?: number;
   error_message?: string;
   executed_at: string;
+  run_count: number;
+  has_result: boolean;
 }
 
 export const queryHistory = writable<QueryHistoryItem[]>([]);
@@ -21,6 +23,8 @@ export const historyStatus = writable<string>("all");
 export const historySortDesc = writable<boolean>(true);
 export const historyStartDate = writable<string | null>(null);
 export const historyEndDate = writable<string | null>(null);
+export const historyConnectionId = writable<number | null>(null);
+export const historyCollapseDuplicates = writable<boolean>(false);
 
 function formatForSqlite(isoString: string): string | null {
   if (!isoString) return null;
@@ -51,6 +55,8 @@ export const loadQueryHistory = async (page: number = 0, userId?: number) => {
         start_date: rawStart ? formatForSqlite(rawStart) : null,
         end_date: rawEnd ? formatForSqlite(rawEnd) : null,
         sort_desc: sortDesc,
+        connection_id: get(historyConnectionId),
+        collapse_duplicates: get(historyCollapseDuplicates),
       },
     });
 
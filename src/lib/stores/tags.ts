@@ -14,8 +14,14 @@ export interface ConnectionTag {
   connection_id: number;
 }
 
+export interface PinnedQueryTag {
+  tag_id: number;
+  pinned_query_id: number;
+}
+
 export const tags = writable<Tag[]>([]);
 export const connectionTags = writable<Record<number, number[]>>({});
+export const pinnedQueryTags = writable<Record<number, number[]>>({});
 
 export const loadTags = async (userId?: number) => {
   const uid = userId || authState.user?.user_id;
@@ -32,6 +38,16 @@ export const loadTags = async (userId?: number) => {
       map[r.connection_id].push(r.tag_id);
     });
     connectionTags.set(map);
+
+    const pinnedRelations = await invoke<PinnedQueryTag[]>(
+      "get_all_pinned_query_tags"
+    );
+    const pinnedMap: Record<number, number[]> = {};
+    pinnedRelations.forEach((r) => {
+      if (!pinnedMap[r.pinned_query_id]) pinnedMap[r.pinned_query_id] = [];
+      pinnedMap[r.pinned_query_id].push(r.tag_id);
+    });
+    pinnedQueryTags.set(pinnedMap);
   } catch (error) {
     console.error("Failed to load tags:", error);
   }
@@ -66,3 +82,18 @@ export const removeTagFromConnection = async (
   await invoke("remove_connection_tag", { tagId, connectionId: connId });
   await loadTags();
 };
+
+export const addTagToPinnedQuery = async (tagId: number, queryId: number) => {
+  await invoke("add_pinned_query_tag", {
+    request: { tag_id: tagId, pinned_query_id: queryId },
+  });
+  await loadTags();
+};
+
+export const removeTagFromPinnedQuery = async (
+  tagId: number,
+  queryId: number
+) => {
+  await invoke("remove_pinned_query_tag", { tagId, pinnedQueryId: queryId });
+  await loadTags();
+};
@@ -0,0 +1,46 @@
+export type AppErrorKind =
+  | "connection"
+  // The app session is missing or expired
+  | "auth"
+  // Postgres rejected the connection's credentials
+  | "database_auth"
+  | "forbidden"
+  | "tls"
+  | "not_found"
+  | "validation"
+  | "postgres"
+  | "internal";
+
+export interface AppError {
+  kind: AppErrorKind;
+  message: string;
+  // Only set for "postgres" errors.
+  code?: string;
+  severity?: string;
+  detail?: string | null;
+  hint?: string | null;
+  position?: number | null;
+  internal_position?: number | null;
+  internal_query?: string | null;
+  context?: string | null;
+  schema?: string | null;
+  table?: string | null;
+  column?: string | null;
+  data_type?: string | null;
+  constraint?: string | null;
+}
+
+export function isAppError(error: unknown): error is AppError {
+  return (
+    typeof error === "object" &&
+    error !== null &&
+    "kind" in error &&
+    "message" in error
+  );
+}
+
+export function errorMessage(error: unknown, fallback = "Unknown error"): string {
+  if (typeof error === "string") return error;
+  if (isAppError(error) || error instanceof Error) return error.message;
+  return fallback;
+}